@@ -1,48 +1,305 @@
-use std::fs::File;
-use std::io;
-use std::io::{BufRead, BufReader};
+use crate::{for_each_line, read_usize, sanitize_division, unix_millis};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::{io, mem};
 
 #[derive(Debug, Default)]
 pub struct ConntrackStatus {
+    pub full_scan: bool,
     count: Option<usize>,
+    max: Option<usize>,
+    entries: Option<ConntrackEntries>,
+    old: ConntrackCounters,
+    new: ConntrackCounters,
+}
+
+/// Error counters from `/proc/net/stat/nf_conntrack`, summed across CPUs.
+#[derive(Debug, Default)]
+struct ConntrackCounters {
+    unix_millis: u64,
+    drop: u64,
+    early_drop: u64,
+    insert_failed: u64,
+    invalid: u64,
+    search_restart: u64,
 }
 
 impl ConntrackStatus {
     pub fn update(&mut self) -> io::Result<()> {
         self.count = None;
-        self.count = Some(Self::sample()?);
+        self.max = None;
+        self.entries = None;
+        if self.full_scan {
+            let entries = ConntrackEntries::sample()?;
+            self.count = Some(entries.count);
+            self.entries = Some(entries);
+        } else {
+            self.count = Some(Self::sample()?);
+        }
+        self.max = read_usize("/proc/sys/net/netfilter/nf_conntrack_max").ok();
+        self.old = mem::take(&mut self.new);
+        self.new = ConntrackCounters::sample()?;
         Ok(())
     }
 
     fn sample() -> io::Result<usize> {
-        let proc_stat = File::open("/proc/net/nf_conntrack")?;
-        let reader = BufReader::new(proc_stat);
+        // Maintained by the kernel, so much faster than counting lines (and doesn't require root).
+        if let Ok(count) = read_usize("/proc/sys/net/netfilter/nf_conntrack_count") {
+            return Ok(count);
+        }
         let mut ret = 0usize;
-        for line in reader.lines() {
-            line?;
+        for_each_line(&["/proc/net/nf_conntrack"], |_| {
             ret = ret.saturating_add(1);
-        }
+        })?;
         Ok(ret)
     }
 
     pub fn sessions(&self) -> Option<usize> {
         self.count
     }
+
+    pub fn usage(&self) -> Option<f32> {
+        sanitize_division(self.count? as u64, self.max? as u64)
+    }
+
+    pub fn drop_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.drop)
+    }
+
+    pub fn early_drop_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.early_drop)
+    }
+
+    pub fn insert_failed_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.insert_failed)
+    }
+
+    pub fn invalid_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.invalid)
+    }
+
+    pub fn search_restart_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.search_restart)
+    }
+
+    /// Calculates rate of change per second.
+    fn rate(&self, counter: impl Fn(&ConntrackCounters) -> u64) -> Option<u64> {
+        let count = counter(&self.new).saturating_sub(counter(&self.old));
+        let millis = self.new.unix_millis.saturating_sub(self.old.unix_millis);
+        count.saturating_mul(1000).checked_div(millis)
+    }
+
+    pub fn entries(&self) -> Option<&ConntrackEntries> {
+        self.entries.as_ref()
+    }
+
+    pub fn top_sources(&self, n: usize) -> Option<Vec<(IpAddr, usize)>> {
+        let entries = self.entries.as_ref()?;
+        let mut sources = entries
+            .sources
+            .iter()
+            .map(|(&address, &count)| (address, count))
+            .collect::<Vec<_>>();
+        sources.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        sources.truncate(n);
+        Some(sources)
+    }
+}
+
+/// Breakdown of the entries in the conntrack table.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConntrackEntries {
+    /// Total number of entries.
+    pub count: usize,
+    /// Number of entries for each layer 4 protocol, e.g. `tcp`, `udp`, or `icmp`.
+    pub protocols: HashMap<String, usize>,
+    /// Number of TCP entries in each conntrack TCP state, e.g. `ESTABLISHED` or `TIME_WAIT`.
+    pub tcp_states: HashMap<String, usize>,
+    /// Number of entries that have seen traffic in both directions, and won't be evicted early
+    /// when the table is full.
+    pub assured: usize,
+    /// Number of entries that haven't seen a reply (yet), e.g. due to a SYN flood.
+    pub unreplied: usize,
+    /// Number of entries for each original source address.
+    sources: HashMap<IpAddr, usize>,
+}
+
+impl ConntrackEntries {
+    fn sample() -> io::Result<Self> {
+        let mut ret = Self::default();
+        for_each_line(&["/proc/net/nf_conntrack"], |line| {
+            ret.count = ret.count.saturating_add(1);
+            ret.add(line);
+        })?;
+        Ok(ret)
+    }
+
+    /// Parses a line like `ipv4 2 tcp 6 431999 ESTABLISHED src=10.0.0.1 dst=10.0.0.2 sport=1234
+    /// dport=22 src=10.0.0.2 dst=10.0.0.1 sport=22 dport=1234 [ASSURED] mark=0 zone=0 use=2`.
+    fn add(&mut self, line: &str) {
+        // l3 protocol name, l3 protocol number, l4 protocol name, l4 protocol number, timeout.
+        let mut tokens = line.split_ascii_whitespace().skip(2);
+        let Some(protocol) = tokens.next() else {
+            return;
+        };
+        increment(&mut self.protocols, protocol);
+        let mut tokens = tokens.skip(2).peekable();
+        if protocol == "tcp" {
+            if let Some(state) = tokens.next_if(|token| !token.contains('=')) {
+                increment(&mut self.tcp_states, state);
+            }
+        }
+
+        let mut source = None;
+        for token in tokens {
+            match token {
+                "[ASSURED]" => self.assured = self.assured.saturating_add(1),
+                "[UNREPLIED]" => self.unreplied = self.unreplied.saturating_add(1),
+                _ => {
+                    // The first source is the original direction.
+                    if let Some(address) = token.strip_prefix("src=") {
+                        source = source.or(address.parse::<IpAddr>().ok());
+                    }
+                }
+            }
+        }
+        if let Some(source) = source {
+            let count = self.sources.entry(source).or_default();
+            *count = count.saturating_add(1);
+        }
+    }
+}
+
+impl ConntrackCounters {
+    fn sample() -> io::Result<Self> {
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
+        // A line of column names, followed by a line of hex values for each CPU.
+        let mut names = Vec::new();
+        for_each_line(&["/proc/net/stat/nf_conntrack"], |line| {
+            let tokens = line.split_ascii_whitespace();
+            if names.is_empty() {
+                names.extend(tokens.map(str::to_owned));
+                return;
+            }
+            for (name, value) in names.iter().zip(tokens) {
+                let field = match name.as_str() {
+                    "drop" => &mut ret.drop,
+                    "early_drop" => &mut ret.early_drop,
+                    "insert_failed" => &mut ret.insert_failed,
+                    "invalid" => &mut ret.invalid,
+                    "search_restart" => &mut ret.search_restart,
+                    _ => continue,
+                };
+                if let Ok(value) = u64::from_str_radix(value, 16) {
+                    *field = field.saturating_add(value);
+                }
+            }
+        })?;
+        Ok(ret)
+    }
+}
+
+fn increment(counts: &mut HashMap<String, usize>, key: &str) {
+    let count = match counts.get_mut(key) {
+        Some(count) => count,
+        None => counts.entry(key.to_owned()).or_default(),
+    };
+    *count = count.saturating_add(1);
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::conntrack::ConntrackEntries;
     use crate::SimpleServerStatus;
+    use std::time::Duration;
 
     #[test]
     fn conntrack() {
         let mut status = SimpleServerStatus::default();
 
         assert_eq!(status.conntrack_sessions(), None);
+        assert_eq!(status.conntrack_usage(), None);
 
         status.update().unwrap();
 
         let connections = status.conntrack_sessions().unwrap();
         println!("conntrack_sessions: {}", connections);
+
+        let usage = status.conntrack_usage().unwrap();
+        println!("conntrack_usage: {}", usage);
+        assert!(usage >= 0.0);
+        assert!(usage <= 1.0);
+    }
+
+    #[test]
+    fn conntrack_errors() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.conntrack_drop_rate(), None);
+        assert_eq!(status.conntrack_invalid_rate(), None);
+
+        status.update().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        status.update().unwrap();
+
+        println!(
+            "conntrack_drop_rate: {}",
+            status.conntrack_drop_rate().unwrap()
+        );
+        println!(
+            "conntrack_early_drop_rate: {}",
+            status.conntrack_early_drop_rate().unwrap()
+        );
+        println!(
+            "conntrack_insert_failed_rate: {}",
+            status.conntrack_insert_failed_rate().unwrap()
+        );
+        println!(
+            "conntrack_invalid_rate: {}",
+            status.conntrack_invalid_rate().unwrap()
+        );
+        println!(
+            "conntrack_search_restart_rate: {}",
+            status.conntrack_search_restart_rate().unwrap()
+        );
+    }
+
+    #[test]
+    fn conntrack_entries() {
+        let mut status = SimpleServerStatus::default().with_conntrack_full_scan(true);
+
+        assert_eq!(status.conntrack_entries(), None);
+        assert_eq!(status.conntrack_top_sources(10), None);
+
+        status.update().unwrap();
+
+        let entries = status.conntrack_entries().unwrap();
+        println!("conntrack_entries: {:?}", entries);
+        assert_eq!(Some(entries.count), status.conntrack_sessions());
+        assert_eq!(entries.protocols.values().sum::<usize>(), entries.count);
+
+        let top_sources = status.conntrack_top_sources(10).unwrap();
+        println!("conntrack_top_sources: {:?}", top_sources);
+        assert!(top_sources.len() <= 10);
+    }
+
+    #[test]
+    fn conntrack_parse() {
+        let mut entries = ConntrackEntries::default();
+        entries.add("ipv4     2 tcp      6 431999 ESTABLISHED src=10.0.0.1 dst=10.0.0.2 sport=1234 dport=22 src=10.0.0.2 dst=10.0.0.1 sport=22 dport=1234 [ASSURED] mark=0 zone=0 use=2");
+        entries.add("ipv4     2 tcp      6 118 SYN_SENT src=10.0.0.1 dst=10.0.0.3 sport=1235 dport=80 [UNREPLIED] src=10.0.0.3 dst=10.0.0.1 sport=80 dport=1235 mark=0 zone=0 use=2");
+        entries.add("ipv6     10 udp      17 29 src=2001:db8::1 dst=2001:db8::2 sport=5353 dport=5353 [UNREPLIED] src=2001:db8::2 dst=2001:db8::1 sport=5353 dport=5353 mark=0 zone=0 use=2");
+
+        assert_eq!(entries.protocols["tcp"], 2);
+        assert_eq!(entries.protocols["udp"], 1);
+        assert_eq!(entries.tcp_states["ESTABLISHED"], 1);
+        assert_eq!(entries.tcp_states["SYN_SENT"], 1);
+        assert_eq!(entries.assured, 1);
+        assert_eq!(entries.unreplied, 2);
+        assert_eq!(entries.sources[&"10.0.0.1".parse().unwrap()], 2);
+        assert_eq!(entries.sources[&"2001:db8::1".parse().unwrap()], 1);
     }
 }
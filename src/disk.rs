@@ -0,0 +1,453 @@
+use crate::{for_each_line, next, sanitize_division, unix_millis};
+use std::collections::{HashMap, HashSet};
+use std::{fs, io, mem};
+
+/// `/proc/diskstats` counts sectors of 512 bytes, regardless of the device's sector size.
+const SECTOR_SIZE: u64 = 512;
+
+/// Whole disks that are excluded by default, because they aren't backed by a disk.
+const DEFAULT_EXCLUDE: &[&str] = &["loop*", "ram*", "zram*"];
+
+/// Whole disks that are excluded by default (unless `friendly_names`), because their I/O is also
+/// counted by the disks they're stacked on.
+const STACKED: &[&str] = &["dm-*", "md*"];
+
+#[derive(Debug, Default)]
+pub struct DiskStatus {
+    pub filter: DiskFilter,
+    old: DiskCounters,
+    new: DiskCounters,
+}
+
+/// Which devices in `/proc/diskstats` to measure, and what to call them.
+#[derive(Debug, Default)]
+pub struct DiskFilter {
+    /// If not empty, only devices matching one of these globs are measured, instead of those not
+    /// matching `DEFAULT_EXCLUDE` (or `STACKED`).
+    pub include: Vec<String>,
+    /// Devices matching any of these globs aren't measured, even if included.
+    pub exclude: Vec<String>,
+    /// Whether to measure partitions, in addition to whole disks (those in `/sys/block`).
+    pub partitions: bool,
+    /// Whether to measure device-mapper and md devices by default, and name them e.g.
+    /// `vg0-root` instead of `dm-0`.
+    pub friendly_names: bool,
+}
+
+#[derive(Debug, Default)]
+struct DiskCounters {
+    unix_millis: u64,
+    devices: HashMap<String, DeviceCounters>,
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct DeviceCounters {
+    reads: u64,
+    read_sectors: u64,
+    read_millis: u64,
+    writes: u64,
+    write_sectors: u64,
+    write_millis: u64,
+    /// Milliseconds spent with at least one I/O in flight.
+    io_millis: u64,
+    /// Milliseconds spent on I/O, multiplied by the number of I/Os in flight.
+    weighted_io_millis: u64,
+}
+
+/// I/O rates and latency of a block device, between the last two calls to `update`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskDevice {
+    /// Kernel name, e.g. `sda` or `nvme0n1`, or with `with_disk_friendly_names`, the name of a
+    /// device-mapper or md device, e.g. `vg0-root` or `data`.
+    pub name: String,
+    /// Bytes read per second.
+    pub read_bandwidth: u64,
+    /// Bytes written per second.
+    pub write_bandwidth: u64,
+    /// Reads completed per second.
+    pub read_iops: u64,
+    /// Writes completed per second.
+    pub write_iops: u64,
+    /// Fraction (0.0..=1.0) of time with at least one I/O in flight. Devices that serve
+    /// requests in parallel (e.g. SSDs) may not be saturated at 1.0.
+    pub utilization: f32,
+    /// Average milliseconds each read took, including time spent queued.
+    pub read_await: f32,
+    /// Average milliseconds each write took, including time spent queued.
+    pub write_await: f32,
+    /// Average number of I/Os in flight.
+    pub queue_depth: f32,
+}
+
+impl DiskStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.old = mem::take(&mut self.new);
+        self.new = DiskCounters::sample(&self.filter)?;
+        Ok(())
+    }
+
+    pub fn read_bandwidth(&self) -> Option<u64> {
+        self.rate(|counters| counters.read_sectors.saturating_mul(SECTOR_SIZE))
+    }
+
+    pub fn write_bandwidth(&self) -> Option<u64> {
+        self.rate(|counters| counters.write_sectors.saturating_mul(SECTOR_SIZE))
+    }
+
+    pub fn read_iops(&self) -> Option<u64> {
+        self.rate(|counters| counters.reads)
+    }
+
+    pub fn write_iops(&self) -> Option<u64> {
+        self.rate(|counters| counters.writes)
+    }
+
+    pub fn devices(&self) -> Option<Vec<DiskDevice>> {
+        let millis = self.millis()?;
+        let mut ret = self
+            .new
+            .devices
+            .iter()
+            .filter_map(|(name, new)| {
+                let old = self.old.devices.get(name)?;
+                let delta =
+                    |counter: fn(&DeviceCounters) -> u64| counter(new).saturating_sub(counter(old));
+                let rate = |counter| delta(counter).saturating_mul(1000) / millis;
+                let average = |numerator, denominator| {
+                    let ret = numerator as f64 / denominator as f64;
+                    if ret.is_finite() {
+                        ret as f32
+                    } else {
+                        0.0
+                    }
+                };
+                Some(DiskDevice {
+                    name: name.clone(),
+                    read_bandwidth: rate(|c| c.read_sectors.saturating_mul(SECTOR_SIZE)),
+                    write_bandwidth: rate(|c| c.write_sectors.saturating_mul(SECTOR_SIZE)),
+                    read_iops: rate(|c| c.reads),
+                    write_iops: rate(|c| c.writes),
+                    utilization: sanitize_division(delta(|c| c.io_millis), millis)
+                        .unwrap_or_default(),
+                    read_await: average(delta(|c| c.read_millis), delta(|c| c.reads)),
+                    write_await: average(delta(|c| c.write_millis), delta(|c| c.writes)),
+                    queue_depth: average(delta(|c| c.weighted_io_millis), millis),
+                })
+            })
+            .collect::<Vec<_>>();
+        ret.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Some(ret)
+    }
+
+    /// Calculates rate of change per second, summed across devices present in both samples, so
+    /// that attaching or removing a device doesn't cause a spike.
+    fn rate(&self, counter: impl Fn(&DeviceCounters) -> u64) -> Option<u64> {
+        let millis = self.millis()?;
+        let count = self
+            .new
+            .devices
+            .iter()
+            .filter_map(|(name, new)| {
+                let old = self.old.devices.get(name)?;
+                Some(counter(new).saturating_sub(counter(old)))
+            })
+            .fold(0u64, u64::saturating_add);
+        Some(count.saturating_mul(1000) / millis)
+    }
+
+    fn millis(&self) -> Option<u64> {
+        Some(self.new.unix_millis.saturating_sub(self.old.unix_millis)).filter(|&m| m > 0)
+    }
+}
+
+impl DiskFilter {
+    fn includes(&self, name: &str, friendly_name: Option<&str>, whole_disk: bool) -> bool {
+        if !whole_disk && !self.partitions {
+            return false;
+        }
+        let matches = |globs: &[String]| {
+            globs.iter().any(|glob| {
+                glob_match(glob, name) || friendly_name.is_some_and(|n| glob_match(glob, n))
+            })
+        };
+        let default_excludes = |globs: &[&str]| globs.iter().any(|glob| glob_match(glob, name));
+        if matches(&self.exclude) {
+            false
+        } else if !self.include.is_empty() {
+            matches(&self.include)
+        } else {
+            !default_excludes(DEFAULT_EXCLUDE)
+                && (self.friendly_names || !default_excludes(STACKED))
+        }
+    }
+
+    /// Maps kernel names (e.g. `dm-0`) to friendly names (e.g. `vg0-root`).
+    fn friendly_names(&self, whole_disks: &HashSet<String>) -> HashMap<String, String> {
+        let mut ret = HashMap::new();
+        if !self.friendly_names {
+            return ret;
+        }
+        for name in whole_disks.iter().filter(|name| name.starts_with("dm-")) {
+            if let Ok(friendly_name) = fs::read_to_string(format!("/sys/block/{name}/dm/name")) {
+                ret.insert(name.clone(), friendly_name.trim_end().to_owned());
+            }
+        }
+        // e.g. /dev/md/data -> ../md127
+        if let Ok(entries) = fs::read_dir("/dev/md") {
+            for entry in entries.flatten() {
+                let Ok(target) = fs::read_link(entry.path()) else {
+                    continue;
+                };
+                let (Some(name), Ok(friendly_name)) = (
+                    target.file_name().and_then(|name| name.to_str()),
+                    entry.file_name().into_string(),
+                ) else {
+                    continue;
+                };
+                ret.insert(name.to_owned(), friendly_name);
+            }
+        }
+        ret
+    }
+}
+
+impl DiskCounters {
+    fn sample(filter: &DiskFilter) -> io::Result<Self> {
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
+        // Partitions are only listed within their disk's directory.
+        let whole_disks = fs::read_dir("/sys/block")?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<HashSet<_>>>()?;
+        let friendly_names = filter.friendly_names(&whole_disks);
+        let mut error = None;
+        for_each_line(&["/proc/diskstats"], |line| {
+            // major minor name reads reads_merged read_sectors read_millis writes writes_merged
+            // write_sectors write_millis in_flight io_millis weighted_io_millis ...
+            let mut tokens = line.split_ascii_whitespace();
+            let Some(name) = tokens.nth(2) else {
+                return;
+            };
+            let friendly_name = friendly_names.get(name).map(String::as_str);
+            if !filter.includes(name, friendly_name, whole_disks.contains(name)) {
+                return;
+            }
+            let name = friendly_name.unwrap_or(name);
+            let mut parse = || -> io::Result<DeviceCounters> {
+                let reads = next(&mut tokens)?;
+                let _reads_merged = next(&mut tokens)?;
+                let read_sectors = next(&mut tokens)?;
+                let read_millis = next(&mut tokens)?;
+                let writes = next(&mut tokens)?;
+                let _writes_merged = next(&mut tokens)?;
+                let write_sectors = next(&mut tokens)?;
+                let write_millis = next(&mut tokens)?;
+                let _in_flight = next(&mut tokens)?;
+                let io_millis = next(&mut tokens)?;
+                let weighted_io_millis = next(&mut tokens)?;
+                Ok(DeviceCounters {
+                    reads,
+                    read_sectors,
+                    read_millis,
+                    writes,
+                    write_sectors,
+                    write_millis,
+                    io_millis,
+                    weighted_io_millis,
+                })
+            };
+            match parse() {
+                Ok(counters) => {
+                    ret.devices.insert(name.to_owned(), counters);
+                }
+                Err(e) => error = Some(e),
+            }
+        })?;
+        if let Some(e) = error {
+            return Err(e);
+        }
+        Ok(ret)
+    }
+}
+
+/// Matches `text` against a `pattern` in which `*` matches any sequence of characters, and `?`
+/// matches any one character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*`, if the rest of the pattern doesn't match.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::disk::{glob_match, DeviceCounters, DiskCounters, DiskFilter, DiskStatus};
+    use crate::SimpleServerStatus;
+    use std::time::Duration;
+
+    #[test]
+    fn disk() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.disk_read_bandwidth(), None);
+        assert_eq!(status.disk_write_bandwidth(), None);
+        assert_eq!(status.disk_devices(), None);
+
+        status.update().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        status.update().unwrap();
+
+        println!(
+            "disk_read_bandwidth: {}",
+            status.disk_read_bandwidth().unwrap()
+        );
+        println!(
+            "disk_write_bandwidth: {}",
+            status.disk_write_bandwidth().unwrap()
+        );
+        println!("disk_read_iops: {}", status.disk_read_iops().unwrap());
+        println!("disk_write_iops: {}", status.disk_write_iops().unwrap());
+
+        let devices = status.disk_devices().unwrap();
+        println!("disk_devices: {:?}", devices);
+        assert!(!devices.is_empty());
+        for device in &devices {
+            assert!((0.0..=1.0).contains(&device.utilization));
+            assert!(device.read_await >= 0.0);
+            assert!(device.write_await >= 0.0);
+            assert!(device.queue_depth >= 0.0);
+        }
+    }
+
+    #[test]
+    fn disk_counters() {
+        let counters = DiskCounters::sample(&DiskFilter::default()).unwrap();
+        println!("disk_counters: {:?}", counters);
+        assert!(counters
+            .devices
+            .keys()
+            .all(|name| !name.starts_with("loop")));
+    }
+
+    #[test]
+    fn disk_hotplug() {
+        let device = |reads| DeviceCounters {
+            reads,
+            ..DeviceCounters::default()
+        };
+        let status = DiskStatus {
+            old: DiskCounters {
+                unix_millis: 1000,
+                devices: [
+                    ("sda".to_owned(), device(100)),
+                    ("sdb".to_owned(), device(100)),
+                ]
+                .into(),
+            },
+            new: DiskCounters {
+                unix_millis: 2000,
+                devices: [
+                    ("sda".to_owned(), device(150)),
+                    ("sdc".to_owned(), device(9999)),
+                ]
+                .into(),
+            },
+            ..DiskStatus::default()
+        };
+        assert_eq!(status.read_iops(), Some(50));
+        let devices = status.devices().unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].name, "sda");
+        assert_eq!(devices[0].read_iops, 50);
+    }
+
+    #[test]
+    fn disk_filter() {
+        let mut status = SimpleServerStatus::default()
+            .with_disk_include(&["*"])
+            .with_disk_exclude(&["vd?"])
+            .with_disk_friendly_names(true);
+
+        status.update().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        status.update().unwrap();
+
+        let devices = status.disk_devices().unwrap();
+        println!("disk_devices: {:?}", devices);
+        assert!(devices
+            .iter()
+            .all(|device| !glob_match("vd?", &device.name)));
+
+        let default = DiskFilter::default();
+        assert!(default.includes("sda", None, true));
+        assert!(!default.includes("sda1", None, false));
+        assert!(!default.includes("loop0", None, true));
+        assert!(!default.includes("dm-0", Some("vg0-root"), true));
+        assert!(!default.includes("md127", None, true));
+
+        let friendly = DiskFilter {
+            friendly_names: true,
+            ..DiskFilter::default()
+        };
+        assert!(friendly.includes("sda", None, true));
+        assert!(!friendly.includes("sda1", None, false));
+        assert!(!friendly.includes("loop0", None, true));
+        assert!(friendly.includes("dm-0", Some("vg0-root"), true));
+        assert!(friendly.includes("md127", Some("data"), true));
+
+        // Globs don't bring in partitions.
+        let all = DiskFilter {
+            include: vec!["*".to_owned()],
+            ..DiskFilter::default()
+        };
+        assert!(all.includes("loop0", None, true));
+        assert!(!all.includes("sda1", None, false));
+
+        let filter = DiskFilter {
+            include: vec!["nvme*".to_owned(), "vg0-*".to_owned()],
+            exclude: vec!["*p1".to_owned()],
+            partitions: true,
+            friendly_names: true,
+        };
+        assert!(filter.includes("nvme0n1", None, true));
+        assert!(filter.includes("nvme0n1p2", None, false));
+        assert!(!filter.includes("nvme0n1p1", None, false));
+        assert!(filter.includes("dm-0", Some("vg0-root"), true));
+        assert!(!filter.includes("sda", None, true));
+    }
+
+    #[test]
+    fn disk_glob() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("sd*", "sda1"));
+        assert!(glob_match("sd?", "sda"));
+        assert!(!glob_match("sd?", "sda1"));
+        assert!(glob_match("*p*1", "nvme0n1p1"));
+        assert!(!glob_match("*p*2", "nvme0n1p1"));
+        assert!(glob_match("md*", "md127"));
+        assert!(!glob_match("md*", "dm-0"));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{next, read_usize, sanitize_division};
+use std::{fs, io};
+
+#[derive(Debug, Default)]
+pub struct FdStatus {
+    handles: Option<usize>,
+    max: Option<usize>,
+    per_process_max: Option<usize>,
+    inodes: Option<usize>,
+}
+
+impl FdStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        *self = Self::default();
+        // allocated unused max
+        let file_nr = fs::read_to_string("/proc/sys/fs/file-nr")?;
+        let mut tokens = file_nr.split_ascii_whitespace();
+        let allocated = next(&mut tokens)?;
+        // Always 0 since Linux 2.6, as unused handles are freed.
+        let unused = next(&mut tokens)?;
+        self.handles = Some(allocated.saturating_sub(unused) as usize);
+        self.max = Some(read_usize("/proc/sys/fs/file-max")?);
+        self.per_process_max = Some(read_usize("/proc/sys/fs/nr_open")?);
+        // nr_inodes nr_free_inodes
+        let inode_nr = fs::read_to_string("/proc/sys/fs/inode-nr")?;
+        let mut tokens = inode_nr.split_ascii_whitespace();
+        let allocated = next(&mut tokens)?;
+        let free = next(&mut tokens)?;
+        self.inodes = Some(allocated.saturating_sub(free) as usize);
+        Ok(())
+    }
+
+    pub fn handles(&self) -> Option<usize> {
+        self.handles
+    }
+
+    pub fn usage(&self) -> Option<f32> {
+        sanitize_division(self.handles? as u64, self.max? as u64)
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+
+    pub fn per_process_max(&self) -> Option<usize> {
+        self.per_process_max
+    }
+
+    pub fn inodes(&self) -> Option<usize> {
+        self.inodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+    use std::fs::File;
+
+    #[test]
+    fn fd() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.file_handles(), None);
+        assert_eq!(status.file_handle_usage(), None);
+
+        let _files = (0..10)
+            .map(|_| File::open("/proc/self/stat").unwrap())
+            .collect::<Vec<_>>();
+        status.update().unwrap();
+
+        let handles = status.file_handles().unwrap();
+        println!("file_handles: {}", handles);
+        assert!(handles >= 10);
+
+        let usage = status.file_handle_usage().unwrap();
+        println!("file_handle_usage: {}", usage);
+        assert!((0.0..=1.0).contains(&usage));
+
+        let max = status.file_handles_max().unwrap();
+        println!("file_handles_max: {}", max);
+        assert!(max >= handles);
+        let per_process_max = status.file_handles_per_process_max().unwrap();
+        println!("file_handles_per_process_max: {}", per_process_max);
+        assert!(per_process_max > 0);
+        let inode_handles = status.inode_handles().unwrap();
+        println!("inode_handles: {}", inode_handles);
+        assert!(inode_handles > 0);
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Recent samples of a fraction (0.0..=1.0) of a resource used.
+#[derive(Debug, Default)]
+pub struct History {
+    /// How long to keep samples for. If zero, no samples are kept.
+    pub window: Duration,
+    /// Unix millis and usage, oldest first.
+    samples: VecDeque<(u64, f32)>,
+}
+
+/// Estimate of when a resource will be fully used, from a linear regression of its recent usage.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Forecast {
+    /// Time until usage reaches 1.0, or `None` if usage isn't increasing.
+    pub time_to_full: Option<Duration>,
+    /// Change in usage per second, e.g. `0.001` if it increases by 0.1% per second.
+    pub rate: f32,
+    /// How well usage fits a line (0.0..=1.0, the coefficient of determination). Values near 0.0
+    /// mean usage is noisy, so `time_to_full` shouldn't be relied on.
+    pub confidence: f32,
+    /// Number of samples the forecast is based on.
+    pub samples: usize,
+}
+
+impl History {
+    pub fn push(&mut self, unix_millis: u64, usage: f32) {
+        let window = self.window.as_millis() as u64;
+        if window == 0 {
+            self.samples.clear();
+            return;
+        }
+        self.samples.push_back((unix_millis, usage));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(oldest, _)| unix_millis.saturating_sub(oldest) > window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Fits a line to usage over time, by least squares. Requires at least 3 samples.
+    pub fn forecast(&self) -> Option<Forecast> {
+        if self.samples.len() < 3 {
+            return None;
+        }
+        let (start, _) = *self.samples.front()?;
+        let (end, _) = *self.samples.back()?;
+        let seconds = |millis: u64| millis.saturating_sub(start) as f64 / 1000.0;
+
+        let n = self.samples.len() as f64;
+        let mean_x = self.samples.iter().map(|&(t, _)| seconds(t)).sum::<f64>() / n;
+        let mean_y = self.samples.iter().map(|&(_, y)| y as f64).sum::<f64>() / n;
+        let (mut covariance, mut variance_x, mut variance_y) = (0.0, 0.0, 0.0);
+        for &(t, y) in &self.samples {
+            let (dx, dy) = (seconds(t) - mean_x, y as f64 - mean_y);
+            covariance += dx * dy;
+            variance_x += dx * dx;
+            variance_y += dy * dy;
+        }
+        if variance_x == 0.0 {
+            // All samples at the same time.
+            return None;
+        }
+        let rate = covariance / variance_x;
+        let confidence = if variance_y == 0.0 {
+            1.0
+        } else {
+            (covariance * covariance / (variance_x * variance_y)).clamp(0.0, 1.0)
+        };
+
+        let fitted = mean_y + rate * (seconds(end) - mean_x);
+        let time_to_full = if rate > 0.0 {
+            Duration::try_from_secs_f64(((1.0 - fitted) / rate).max(0.0)).ok()
+        } else {
+            None
+        };
+        Some(Forecast {
+            time_to_full,
+            rate: rate as f32,
+            confidence: confidence as f32,
+            samples: self.samples.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::forecast::History;
+    use std::time::Duration;
+
+    #[test]
+    fn forecast() {
+        let mut history = History::default();
+        history.push(0, 0.5);
+        history.push(1000, 0.6);
+        history.push(2000, 0.7);
+        assert_eq!(history.forecast(), None);
+
+        history.window = Duration::from_secs(10);
+        for (seconds, usage) in [(0, 0.5), (1, 0.5), (2, 0.6)] {
+            history.push(seconds * 1000, usage);
+        }
+        assert!(history.forecast().is_some());
+
+        // Old samples are forgotten.
+        for seconds in 100..=110 {
+            history.push(seconds * 1000, 0.5 + (seconds - 100) as f32 * 0.01);
+        }
+        let forecast = history.forecast().unwrap();
+        println!("forecast: {:?}", forecast);
+        assert_eq!(forecast.samples, 11);
+        assert!((forecast.rate - 0.01).abs() < 0.0001);
+        assert!(forecast.confidence > 0.99);
+        let time_to_full = forecast.time_to_full.unwrap().as_secs_f32();
+        assert!((time_to_full - 40.0).abs() < 0.1);
+
+        for seconds in 200..=210 {
+            history.push(seconds * 1000, 0.5);
+        }
+        let forecast = history.forecast().unwrap();
+        assert_eq!(forecast.time_to_full, None);
+        assert_eq!(forecast.confidence, 1.0);
+    }
+}
@@ -0,0 +1,407 @@
+use crate::forecast::{Forecast, History};
+use crate::{sanitize_division, unix_millis};
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::ffi::{CString, OsString};
+#[cfg(target_os = "linux")]
+use std::mem::MaybeUninit;
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{io, iter};
+
+/// Filesystems that don't store data on a disk, or whose usage is meaningless (e.g. read-only
+/// images, which are always full).
+#[cfg(target_os = "linux")]
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "erofs",
+    "fusectl",
+    "hugetlbfs",
+    "iso9660",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "squashfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Filesystems served over the network, which are skipped by default because `statvfs` blocks
+/// while the server is unreachable.
+#[cfg(target_os = "linux")]
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "9p",
+    "afs",
+    "ceph",
+    "cifs",
+    "davfs",
+    "fuse.gcsfuse",
+    "fuse.glusterfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "fuse.sshfs",
+    "glusterfs",
+    "lustre",
+    "ncpfs",
+    "nfs",
+    "nfs4",
+    "smb3",
+    "smbfs",
+];
+
+#[derive(Debug, Default)]
+pub struct FsStatus {
+    pub forecast_window: Duration,
+    /// Whether to measure `NETWORK_FILESYSTEMS`.
+    pub network: bool,
+    filesystems: Option<Vec<Filesystem>>,
+    /// Usage history of each mount point.
+    histories: HashMap<PathBuf, History>,
+}
+
+impl FsStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.filesystems = None;
+        let filesystems = Filesystem::sample(self.network)?;
+
+        let unix_millis = unix_millis();
+        self.histories.retain(|mount_point, _| {
+            filesystems
+                .iter()
+                .any(|filesystem| &filesystem.mount_point == mount_point)
+        });
+        for filesystem in &filesystems {
+            let Some(usage) = filesystem.usage() else {
+                continue;
+            };
+            let history = match self.histories.get_mut(&filesystem.mount_point) {
+                Some(history) => history,
+                None => self
+                    .histories
+                    .entry(filesystem.mount_point.clone())
+                    .or_default(),
+            };
+            history.window = self.forecast_window;
+            history.push(unix_millis, usage);
+        }
+
+        self.filesystems = Some(filesystems);
+        Ok(())
+    }
+
+    pub fn filesystems(&self) -> Option<&[Filesystem]> {
+        self.filesystems.as_deref()
+    }
+
+    /// Finds the filesystem mounted closest to `path`.
+    pub fn filesystem(&self, path: &Path) -> Option<&Filesystem> {
+        self.filesystems
+            .as_ref()?
+            .iter()
+            .flat_map(|filesystem| {
+                iter::once(&filesystem.mount_point)
+                    .chain(&filesystem.bind_mounts)
+                    .map(move |mount_point| (filesystem, mount_point))
+            })
+            .filter(|(_, mount_point)| path.starts_with(mount_point))
+            .max_by_key(|(_, mount_point)| mount_point.components().count())
+            .map(|(filesystem, _)| filesystem)
+    }
+
+    pub fn forecast(&self, path: &Path) -> Option<Forecast> {
+        let filesystem = self.filesystem(path)?;
+        self.histories.get(&filesystem.mount_point)?.forecast()
+    }
+}
+
+/// Space and inode usage of a mounted filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filesystem {
+    /// Where the filesystem is mounted, e.g. `/home`.
+    pub mount_point: PathBuf,
+    /// Other places the same filesystem is mounted (e.g. bind mounts), which aren't listed
+    /// separately.
+    pub bind_mounts: Vec<PathBuf>,
+    /// What is mounted, e.g. `/dev/sda1`.
+    pub source: String,
+    /// Type of filesystem, e.g. `ext4`.
+    pub fs_type: String,
+    /// Bytes of space, including space reserved for root.
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+    /// Bytes of space available to unprivileged users.
+    pub available_bytes: u64,
+    pub total_inodes: u64,
+    pub used_inodes: u64,
+    /// Inodes available to unprivileged users.
+    pub available_inodes: u64,
+}
+
+impl Filesystem {
+    #[cfg(target_os = "linux")]
+    fn sample(network: bool) -> io::Result<Vec<Self>> {
+        // Not `read_to_string`, as mount points needn't be UTF-8.
+        let mountinfo = std::fs::read("/proc/self/mountinfo")?;
+        Ok(Self::parse(&mountinfo, network, Self::statvfs))
+    }
+
+    /// Filesystems are only measured on Linux.
+    #[cfg(not(target_os = "linux"))]
+    fn sample(_network: bool) -> io::Result<Vec<Self>> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn parse(
+        mountinfo: &[u8],
+        network: bool,
+        mut statvfs: impl FnMut(Vec<u8>, &str, &str) -> io::Result<Self>,
+    ) -> Vec<Self> {
+        let mut ret = Vec::<Self>::new();
+        // Index in `ret` of each device (major:minor) already measured.
+        let mut devices = HashMap::<&[u8], usize>::new();
+        for line in mountinfo.split(|&byte| byte == b'\n') {
+            // id parent major:minor root mount_point options [optional...] - fs_type source ...
+            let mut tokens = line
+                .split(u8::is_ascii_whitespace)
+                .filter(|token| !token.is_empty());
+            let (Some(device), Some(mount_point)) = (tokens.nth(2), tokens.nth(1)) else {
+                continue;
+            };
+            let mut tokens = tokens.skip_while(|&token| token != b"-").skip(1);
+            let (Some(fs_type), Some(source)) = (tokens.next(), tokens.next()) else {
+                continue;
+            };
+            let fs_type = String::from_utf8_lossy(fs_type);
+            if PSEUDO_FILESYSTEMS.contains(&&*fs_type)
+                || (!network && NETWORK_FILESYSTEMS.contains(&&*fs_type))
+                // e.g. a container's root filesystem, whose space is that of the filesystem
+                // holding its upper layer. Unless it's our own root, i.e. we're in a container.
+                || (fs_type == "overlay" && mount_point != b"/")
+            {
+                continue;
+            }
+            let mount_point = unescape(mount_point);
+            if let Some(&index) = devices.get(device) {
+                let mount_point = PathBuf::from(OsString::from_vec(mount_point));
+                ret[index].bind_mounts.push(mount_point);
+                continue;
+            }
+            // Skip filesystems that can't be measured, e.g. due to permissions.
+            let source = String::from_utf8_lossy(source);
+            if let Ok(filesystem) = statvfs(mount_point, &source, &fs_type) {
+                devices.insert(device, ret.len());
+                ret.push(filesystem);
+            }
+        }
+        ret
+    }
+
+    // `fsblkcnt_t`, `fsfilcnt_t`, and `c_ulong` are 32 bits on some targets.
+    #[cfg(target_os = "linux")]
+    #[allow(clippy::unnecessary_cast)]
+    fn statvfs(mount_point: Vec<u8>, source: &str, fs_type: &str) -> io::Result<Self> {
+        let path = CString::new(mount_point)?;
+        let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `path` is nul-terminated, and `stat` is valid for writes.
+        if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `statvfs` succeeded, so initialized `stat`.
+        let stat = unsafe { stat.assume_init() };
+
+        let bytes = |blocks| (blocks as u64).saturating_mul(stat.f_frsize as u64);
+        Ok(Self {
+            mount_point: PathBuf::from(OsString::from_vec(path.into_bytes())),
+            bind_mounts: Vec::new(),
+            source: source.to_owned(),
+            fs_type: fs_type.to_owned(),
+            total_bytes: bytes(stat.f_blocks),
+            used_bytes: bytes(stat.f_blocks).saturating_sub(bytes(stat.f_bfree)),
+            available_bytes: bytes(stat.f_bavail),
+            total_inodes: stat.f_files as u64,
+            used_inodes: (stat.f_files as u64).saturating_sub(stat.f_ffree as u64),
+            available_inodes: stat.f_favail as u64,
+        })
+    }
+
+    /// Returns the fraction (0.0..=1.0) of space used, of the space available to unprivileged
+    /// users (like `df`).
+    pub fn usage(&self) -> Option<f32> {
+        sanitize_division(
+            self.used_bytes,
+            self.used_bytes.saturating_add(self.available_bytes),
+        )
+    }
+
+    /// Returns the fraction (0.0..=1.0) of inodes used, of the inodes available to unprivileged
+    /// users.
+    ///
+    /// Returns `None` for filesystems without a fixed number of inodes (e.g. btrfs).
+    pub fn inode_usage(&self) -> Option<f32> {
+        sanitize_division(
+            self.used_inodes,
+            self.used_inodes.saturating_add(self.available_inodes),
+        )
+    }
+}
+
+/// Decodes the octal escapes (e.g. `\040` for space) in `/proc/self/mountinfo`.
+#[cfg(target_os = "linux")]
+fn unescape(bytes: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        if let Some(byte) = octal {
+            ret.push(byte);
+            i += 4;
+        } else {
+            ret.push(bytes[i]);
+            i += 1;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(target_os = "linux")]
+    use crate::fs::{unescape, Filesystem, FsStatus};
+    use crate::SimpleServerStatus;
+    #[cfg(target_os = "linux")]
+    use std::ffi::OsString;
+    #[cfg(target_os = "linux")]
+    use std::os::unix::ffi::OsStringExt;
+    #[cfg(target_os = "linux")]
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    #[test]
+    fn fs() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.filesystems(), None);
+        assert_eq!(status.fs_usage("/"), None);
+
+        status.update().unwrap();
+
+        let filesystems = status.filesystems().unwrap();
+        println!("filesystems: {:?}", filesystems);
+        assert!(filesystems
+            .iter()
+            .all(|filesystem| filesystem.fs_type != "proc"));
+        for filesystem in filesystems {
+            assert!(filesystem.used_bytes <= filesystem.total_bytes);
+            assert!(filesystem.used_inodes <= filesystem.total_inodes);
+        }
+
+        let usage = status.fs_usage("/").unwrap();
+        println!("fs_usage: {}", usage);
+        assert!((0.0..=1.0).contains(&usage));
+        assert_eq!(status.fs_usage("relative"), None);
+    }
+
+    #[test]
+    fn fs_forecast() {
+        let mut status = SimpleServerStatus::default();
+        assert_eq!(status.fs_forecast("/"), None);
+        for _ in 0..3 {
+            status.update().unwrap();
+        }
+        assert_eq!(status.fs_forecast("/"), None);
+
+        let mut status =
+            SimpleServerStatus::default().with_forecast_window(Duration::from_secs(60));
+        for _ in 0..3 {
+            status.update().unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let forecast = status.fs_forecast("/").unwrap();
+        println!("fs_forecast: {:?}", forecast);
+        assert_eq!(forecast.samples, 3);
+        assert!((0.0..=1.0).contains(&forecast.confidence));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn fs_parse() {
+        let mountinfo = b"\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+23 22 0:22 / /proc rw,nosuid shared:12 - proc proc rw
+24 22 8:1 /srv/data /mnt/data rw,relatime shared:1 - ext4 /dev/sda1 rw
+25 22 8:2 / /home rw,relatime shared:2 - xfs /dev/sda2 rw
+26 22 0:50 / /mnt/nfs rw,relatime shared:3 - nfs4 server:/export rw
+27 22 0:51 / /var/lib/docker/overlay2/abc/merged rw - overlay overlay rw,upperdir=/x
+28 22 8:3 / /mnt/\xff\\040b rw,relatime shared:4 - ext4 /dev/sda3 rw
+";
+        let statvfs = |mount_point, source: &str, fs_type: &str| {
+            Ok(Filesystem {
+                mount_point: PathBuf::from(OsString::from_vec(mount_point)),
+                bind_mounts: Vec::new(),
+                source: source.to_owned(),
+                fs_type: fs_type.to_owned(),
+                total_bytes: 0,
+                used_bytes: 0,
+                available_bytes: 0,
+                total_inodes: 0,
+                used_inodes: 0,
+                available_inodes: 0,
+            })
+        };
+
+        let filesystems = Filesystem::parse(mountinfo, false, statvfs);
+        let mount_points = filesystems
+            .iter()
+            .map(|filesystem| filesystem.mount_point.as_path())
+            .collect::<Vec<_>>();
+        let non_utf8 = PathBuf::from(OsString::from_vec(b"/mnt/\xff b".to_vec()));
+        assert_eq!(
+            mount_points,
+            [Path::new("/"), Path::new("/home"), &non_utf8]
+        );
+        assert_eq!(filesystems[0].bind_mounts, [Path::new("/mnt/data")]);
+        let status = FsStatus {
+            filesystems: Some(filesystems),
+            ..FsStatus::default()
+        };
+        let filesystem = status.filesystem(Path::new("/mnt/data/file")).unwrap();
+        assert_eq!(filesystem.mount_point, Path::new("/"));
+
+        let filesystems = Filesystem::parse(mountinfo, true, statvfs);
+        assert_eq!(filesystems.len(), 4);
+        assert_eq!(filesystems[2].fs_type, "nfs4");
+
+        let container = b"28 1 0:52 / / rw - overlay overlay rw,upperdir=/x\n";
+        let filesystems = Filesystem::parse(container, false, statvfs);
+        assert_eq!(filesystems.len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn fs_unescape() {
+        assert_eq!(unescape(b"/mnt/a\\040b"), b"/mnt/a b");
+        assert_eq!(unescape(b"/mnt/a\\134"), b"/mnt/a\\");
+        assert_eq!(unescape(b"/mnt/a\\"), b"/mnt/a\\");
+    }
+}
@@ -1,4 +1,10 @@
+#[cfg(any(feature = "tcp", feature = "udp"))]
+use std::collections::HashMap;
+use std::fs::File;
 use std::io;
+use std::io::{BufRead, BufReader};
+#[allow(unused)]
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::SplitAsciiWhitespace;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -6,14 +12,74 @@ use std::time::{SystemTime, UNIX_EPOCH};
 mod conntrack;
 #[cfg(feature = "cpu")]
 mod cpu;
+#[cfg(feature = "disk")]
+mod disk;
+#[cfg(feature = "fd")]
+mod fd;
+#[cfg(any(feature = "fs", feature = "ram"))]
+mod forecast;
+#[cfg(feature = "fs")]
+mod fs;
+#[cfg(feature = "mdstat")]
+mod mdstat;
 #[cfg(feature = "net")]
 mod net;
+// `NETLINK_SOCK_DIAG` only exists on Linux. Elsewhere, the `/proc` fallback returns `Err`.
+#[cfg(all(
+    feature = "netlink",
+    target_os = "linux",
+    any(feature = "tcp", feature = "udp")
+))]
+mod netlink;
+#[cfg(feature = "netstat")]
+mod netstat;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+mod owner;
+#[cfg(feature = "process")]
+mod process;
 #[cfg(feature = "ram")]
 mod ram;
+#[cfg(feature = "raw")]
+mod raw;
+#[cfg(feature = "snmp")]
+mod snmp;
+#[cfg(feature = "sockstat")]
+mod sockstat;
 #[cfg(feature = "tcp")]
 mod tcp;
 #[cfg(feature = "udp")]
 mod udp;
+#[cfg(feature = "udplite")]
+mod udplite;
+#[cfg(feature = "unix")]
+mod unix;
+#[cfg(feature = "zfs")]
+mod zfs;
+
+#[cfg(feature = "conntrack")]
+pub use conntrack::ConntrackEntries;
+#[cfg(feature = "disk")]
+pub use disk::DiskDevice;
+#[cfg(any(feature = "fs", feature = "ram"))]
+pub use forecast::Forecast;
+#[cfg(feature = "fs")]
+pub use fs::Filesystem;
+#[cfg(feature = "mdstat")]
+pub use mdstat::{MdArray, MdMember, MdSync};
+#[cfg(any(feature = "tcp", feature = "udp"))]
+pub use owner::SocketOwner;
+#[cfg(feature = "process")]
+pub use process::Process;
+#[cfg(feature = "sockstat")]
+pub use sockstat::Sockstat;
+#[cfg(all(feature = "tcp", feature = "netlink"))]
+pub use tcp::TcpHealth;
+#[cfg(feature = "tcp")]
+pub use tcp::{TcpAcceptQueue, TcpPeer, TcpQueues, TcpState, TcpStates};
+#[cfg(feature = "unix")]
+pub use unix::UnixSockets;
+#[cfg(feature = "zfs")]
+pub use zfs::ZfsArc;
 
 /// Provides simple APIs to measure status of Linux servers.
 #[derive(Default)]
@@ -22,14 +88,40 @@ pub struct SimpleServerStatus {
     conntrack: conntrack::ConntrackStatus,
     #[cfg(feature = "cpu")]
     cpu: cpu::CpuStatus,
+    #[cfg(feature = "disk")]
+    disk: disk::DiskStatus,
+    #[cfg(feature = "fd")]
+    fd: fd::FdStatus,
+    #[cfg(feature = "fs")]
+    fs: fs::FsStatus,
+    #[cfg(feature = "mdstat")]
+    mdstat: mdstat::MdstatStatus,
     #[cfg(feature = "net")]
     net: net::NetStatus,
+    #[cfg(feature = "netstat")]
+    netstat: netstat::NetstatStatus,
+    #[cfg(feature = "process")]
+    process: process::ProcessStatus,
     #[cfg(feature = "ram")]
     ram: ram::RamStatus,
+    #[cfg(feature = "raw")]
+    raw: raw::RawStatus,
+    #[cfg(feature = "snmp")]
+    snmp: snmp::SnmpStatus,
+    #[cfg(feature = "sockstat")]
+    sockstat: sockstat::SockstatStatus,
+    #[cfg(feature = "sockstat")]
+    sockstat_mode: bool,
     #[cfg(feature = "tcp")]
     tcp: tcp::TcpStatus,
-    #[cfg(feature = "tcp")]
+    #[cfg(feature = "udp")]
     udp: udp::UdpStatus,
+    #[cfg(feature = "udplite")]
+    udplite: udplite::UdpLiteStatus,
+    #[cfg(feature = "unix")]
+    unix: unix::UnixStatus,
+    #[cfg(feature = "zfs")]
+    zfs: zfs::ZfsStatus,
 }
 
 impl SimpleServerStatus {
@@ -38,6 +130,164 @@ impl SimpleServerStatus {
         Self::default()
     }
 
+    /// If enabled, `tcp_connections` and `udp_sockets` are measured via `/proc/net/sockstat`,
+    /// which takes constant time no matter how many sockets there are, instead of by counting
+    /// every socket. Other TCP and UDP measurements (e.g. `tcp_states`) are then skipped, and
+    /// will return `None`.
+    ///
+    /// `tcp_connections` may differ slightly, as it won't include half-open connections.
+    #[cfg(feature = "sockstat")]
+    pub fn with_sockstat_mode(mut self, enabled: bool) -> Self {
+        self.sockstat_mode = enabled;
+        self
+    }
+
+    /// If enabled, `update` parses every entry in `/proc/net/nf_conntrack`, which is required for
+    /// `conntrack_entries` and `conntrack_top_sources`. This may take seconds for large tables,
+    /// and requires root.
+    #[cfg(feature = "conntrack")]
+    pub fn with_conntrack_full_scan(mut self, enabled: bool) -> Self {
+        self.conntrack.full_scan = enabled;
+        self
+    }
+
+    /// Measures only disks matching any of the given globs (where `*` matches any characters, and
+    /// `?` matches one), e.g. `["nvme*n1", "sd?"]`.
+    ///
+    /// By default, all whole disks (those in `/sys/block`) are measured, excluding `loop`, `ram`,
+    /// and `zram` devices, and `dm` and `md` devices (since their I/O is counted by the disks they
+    /// use) unless `with_disk_friendly_names` is enabled. Partitions are only measured if
+    /// `with_disk_partitions` is enabled.
+    #[cfg(feature = "disk")]
+    pub fn with_disk_include(mut self, globs: &[&str]) -> Self {
+        self.disk.filter.include = globs.iter().map(|&glob| glob.to_owned()).collect();
+        self
+    }
+
+    /// Skips disks and partitions matching any of the given globs, even if included by
+    /// `with_disk_include`.
+    #[cfg(feature = "disk")]
+    pub fn with_disk_exclude(mut self, globs: &[&str]) -> Self {
+        self.disk.filter.exclude = globs.iter().map(|&glob| glob.to_owned()).collect();
+        self
+    }
+
+    /// If enabled, partitions (e.g. `sda1`) are measured in addition to whole disks, subject to
+    /// `with_disk_include` and `with_disk_exclude`.
+    ///
+    /// Aggregate rates (e.g. `disk_read_bandwidth`) then count I/O to a partition twice, once for
+    /// the partition, and once for its disk.
+    #[cfg(feature = "disk")]
+    pub fn with_disk_partitions(mut self, enabled: bool) -> Self {
+        self.disk.filter.partitions = enabled;
+        self
+    }
+
+    /// If enabled, device-mapper and md devices are measured too (unless configured otherwise with
+    /// `with_disk_include`), and named e.g. `vg0-root` (from `/sys/block/dm-*/dm/name`) and
+    /// `data` (from `/dev/md`) instead of `dm-0` and `md127`. Globs match either name.
+    ///
+    /// Aggregate rates (e.g. `disk_read_bandwidth`) then count I/O to these devices twice, once
+    /// for the device, and once for the disks it uses.
+    #[cfg(feature = "disk")]
+    pub fn with_disk_friendly_names(mut self, enabled: bool) -> Self {
+        self.disk.filter.friendly_names = enabled;
+        self
+    }
+
+    /// If enabled, `update` counts TCP sockets per local port, which is required for
+    /// `tcp_local_port_states` and `tcp_local_ports`. This takes memory proportional to the number
+    /// of ports in use, which includes the ephemeral port of every outgoing connection.
+    #[cfg(feature = "tcp")]
+    pub fn with_tcp_local_ports(mut self, enabled: bool) -> Self {
+        self.tcp.local_ports = enabled;
+        self
+    }
+
+    /// If enabled, `update` counts TCP sockets per remote address, which is required for
+    /// `tcp_top_peers` and `tcp_top_peer_networks`. This takes memory proportional to the number
+    /// of peers.
+    #[cfg(feature = "tcp")]
+    pub fn with_tcp_peers(mut self, enabled: bool) -> Self {
+        self.tcp.peers = enabled;
+        self
+    }
+
+    /// Keeps a history of `ram_usage` and each filesystem's usage over the given window (e.g. one
+    /// hour), to forecast when they'll be full via `ram_forecast` and `fs_forecast`.
+    ///
+    /// By default, no history is kept.
+    #[cfg(any(feature = "fs", feature = "ram"))]
+    pub fn with_forecast_window(mut self, window: std::time::Duration) -> Self {
+        #[cfg(feature = "fs")]
+        {
+            self.fs.forecast_window = window;
+        }
+        #[cfg(feature = "ram")]
+        {
+            self.ram.history.window = window;
+        }
+        self
+    }
+
+    /// If enabled, network filesystems (e.g. NFS, CIFS, and SSHFS) are measured too, in
+    /// `filesystems` and related methods. If a server is unreachable, `update` may then block
+    /// until it responds.
+    #[cfg(feature = "fs")]
+    pub fn with_fs_network(mut self, enabled: bool) -> Self {
+        self.fs.network = enabled;
+        self
+    }
+
+    /// If enabled, the part of the ZFS ARC above its minimum size (`c_min`) counts as reclaimable
+    /// in `ram_usage`, since the kernel shrinks it under memory pressure, like the page cache.
+    #[cfg(feature = "zfs")]
+    pub fn with_zfs_arc_reclaimable(mut self, enabled: bool) -> Self {
+        self.zfs.arc_reclaimable = enabled;
+        self
+    }
+
+    /// Measures the process with the given pid, instead of the current process, in `process` and
+    /// related methods.
+    #[cfg(feature = "process")]
+    pub fn with_process_pid(mut self, pid: u32) -> Self {
+        self.process.pid = Some(pid);
+        self
+    }
+
+    /// If enabled, `update` also measures each thread of the process (see `with_process_pid`),
+    /// which is required for `process_thread_cpu_usage`. This takes time proportional to the
+    /// number of threads.
+    #[cfg(feature = "process")]
+    pub fn with_process_threads(mut self, enabled: bool) -> Self {
+        self.process.threads = enabled;
+        self
+    }
+
+    #[allow(unused)]
+    fn ram_reclaimable(&self) -> u64 {
+        #[cfg(feature = "zfs")]
+        {
+            self.zfs.reclaimable()
+        }
+        #[cfg(not(feature = "zfs"))]
+        {
+            0
+        }
+    }
+
+    #[allow(unused)]
+    fn sockstat_mode(&self) -> bool {
+        #[cfg(feature = "sockstat")]
+        {
+            self.sockstat_mode
+        }
+        #[cfg(not(feature = "sockstat"))]
+        {
+            false
+        }
+    }
+
     /// Make a new measurement, clearing the old one.
     ///
     /// If an error occurs while updating any one component, all the other updates will still be
@@ -53,22 +303,71 @@ impl SimpleServerStatus {
         {
             result = self.cpu.update().and(result);
         }
+        #[cfg(feature = "disk")]
+        {
+            result = self.disk.update().and(result);
+        }
+        #[cfg(feature = "fd")]
+        {
+            result = self.fd.update().and(result);
+        }
+        #[cfg(feature = "fs")]
+        {
+            result = self.fs.update().and(result);
+        }
+        #[cfg(feature = "mdstat")]
+        {
+            result = self.mdstat.update().and(result);
+        }
         #[cfg(feature = "net")]
         {
             result = self.net.update().and(result);
         }
+        #[cfg(feature = "netstat")]
+        {
+            result = self.netstat.update().and(result);
+        }
+        #[cfg(feature = "process")]
+        {
+            result = self.process.update().and(result);
+        }
+        // Before `ram`, which may count some of the ARC as reclaimable.
+        #[cfg(feature = "zfs")]
+        {
+            result = self.zfs.update().and(result);
+        }
         #[cfg(feature = "ram")]
         {
-            result = self.ram.update().and(result);
+            result = self.ram.update(self.ram_reclaimable()).and(result);
         }
-        #[cfg(feature = "tcp")]
+        #[cfg(feature = "raw")]
         {
+            result = self.raw.update().and(result);
+        }
+        #[cfg(feature = "snmp")]
+        {
+            result = self.snmp.update().and(result);
+        }
+        #[cfg(feature = "sockstat")]
+        {
+            result = self.sockstat.update().and(result);
+        }
+        #[cfg(feature = "tcp")]
+        if !self.sockstat_mode() {
             result = self.tcp.update().and(result);
         }
         #[cfg(feature = "udp")]
-        {
+        if !self.sockstat_mode() {
             result = self.udp.update().and(result);
         }
+        #[cfg(feature = "udplite")]
+        {
+            result = self.udplite.update().and(result);
+        }
+        #[cfg(feature = "unix")]
+        {
+            result = self.unix.update().and(result);
+        }
         result
     }
 
@@ -78,6 +377,68 @@ impl SimpleServerStatus {
         self.conntrack.sessions()
     }
 
+    /// Returns the fraction (0.0..=1.0) of the conntrack table used as of the last call to
+    /// `update`.
+    ///
+    /// When the table is full, new connections (e.g. new players) are silently dropped.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_usage(&self) -> Option<f32> {
+        self.conntrack.usage()
+    }
+
+    /// Returns the number of packets per second dropped because a conntrack entry couldn't be
+    /// created, e.g. because the table was full, between the last two calls to `update`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_drop_rate(&self) -> Option<u64> {
+        self.conntrack.drop_rate()
+    }
+
+    /// Returns the number of conntrack entries per second evicted to make room for new ones,
+    /// between the last two calls to `update`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_early_drop_rate(&self) -> Option<u64> {
+        self.conntrack.early_drop_rate()
+    }
+
+    /// Returns the number of conntrack entries per second that couldn't be inserted, e.g. due to
+    /// a race with another CPU, between the last two calls to `update`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_insert_failed_rate(&self) -> Option<u64> {
+        self.conntrack.insert_failed_rate()
+    }
+
+    /// Returns the number of packets per second that conntrack couldn't track, e.g. because they
+    /// were malformed or out of window, between the last two calls to `update`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_invalid_rate(&self) -> Option<u64> {
+        self.conntrack.invalid_rate()
+    }
+
+    /// Returns the number of conntrack table lookups per second that had to be restarted due to
+    /// concurrent changes, between the last two calls to `update`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_search_restart_rate(&self) -> Option<u64> {
+        self.conntrack.search_restart_rate()
+    }
+
+    /// Returns a breakdown of conntrack entries by protocol, TCP state, and status, as of the last
+    /// call to `update`.
+    ///
+    /// Requires `with_conntrack_full_scan`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_entries(&self) -> Option<&ConntrackEntries> {
+        self.conntrack.entries()
+    }
+
+    /// Returns up to `n` source addresses with the most conntrack entries, as of the last call to
+    /// `update`, in descending order of count.
+    ///
+    /// Requires `with_conntrack_full_scan`.
+    #[cfg(feature = "conntrack")]
+    pub fn conntrack_top_sources(&self, n: usize) -> Option<Vec<(IpAddr, usize)>> {
+        self.conntrack.top_sources(n)
+    }
+
     /// Returns the fraction (0.0..=1.0) of cpu used between the last two calls to `update`.
     #[cfg(feature = "cpu")]
     pub fn cpu_usage(&self) -> Option<f32> {
@@ -122,6 +483,193 @@ impl SimpleServerStatus {
         self.net.transmission_bandwidth()
     }
 
+    /// Returns the average bytes read from disk per second between the last two calls to `update`.
+    ///
+    /// Aggregates all measured disks (see `with_disk_include`).
+    #[cfg(feature = "disk")]
+    pub fn disk_read_bandwidth(&self) -> Option<u64> {
+        self.disk.read_bandwidth()
+    }
+
+    /// Returns the average bytes written to disk per second between the last two calls to
+    /// `update`.
+    ///
+    /// Aggregates all measured disks (see `with_disk_include`).
+    #[cfg(feature = "disk")]
+    pub fn disk_write_bandwidth(&self) -> Option<u64> {
+        self.disk.write_bandwidth()
+    }
+
+    /// Returns the average disk reads completed per second between the last two calls to
+    /// `update`.
+    ///
+    /// Aggregates all measured disks (see `with_disk_include`).
+    #[cfg(feature = "disk")]
+    pub fn disk_read_iops(&self) -> Option<u64> {
+        self.disk.read_iops()
+    }
+
+    /// Returns the average disk writes completed per second between the last two calls to
+    /// `update`.
+    ///
+    /// Aggregates all measured disks (see `with_disk_include`).
+    #[cfg(feature = "disk")]
+    pub fn disk_write_iops(&self) -> Option<u64> {
+        self.disk.write_iops()
+    }
+
+    /// Returns I/O rates, utilization, and latency of each measured disk (see
+    /// `with_disk_include`) between the last two calls to `update`, sorted by name.
+    ///
+    /// Disks attached between the last two calls are omitted until the next call.
+    #[cfg(feature = "disk")]
+    pub fn disk_devices(&self) -> Option<Vec<DiskDevice>> {
+        self.disk.devices()
+    }
+
+    /// Returns the space and inode usage of each mounted filesystem (except pseudo-filesystems
+    /// like `proc`, and network filesystems unless `with_fs_network` is enabled) as of the last
+    /// call to `update`, in mount order.
+    ///
+    /// A filesystem mounted in several places (e.g. via bind mounts) is listed once.
+    #[cfg(feature = "fs")]
+    pub fn filesystems(&self) -> Option<&[Filesystem]> {
+        self.fs.filesystems()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of space used on the filesystem containing the absolute
+    /// `path`, as of the last call to `update`.
+    #[cfg(feature = "fs")]
+    pub fn fs_usage(&self, path: impl AsRef<std::path::Path>) -> Option<f32> {
+        self.fs.filesystem(path.as_ref())?.usage()
+    }
+
+    /// Forecasts when the filesystem containing the absolute `path` will be full, from its usage
+    /// over the last `with_forecast_window`.
+    ///
+    /// Requires at least 3 calls to `update` within the window.
+    #[cfg(feature = "fs")]
+    pub fn fs_forecast(&self, path: impl AsRef<std::path::Path>) -> Option<Forecast> {
+        self.fs.forecast(path.as_ref())
+    }
+
+    /// Returns the software RAID arrays in `/proc/mdstat` as of the last call to `update`,
+    /// including whether they're degraded and their resync/recovery progress.
+    ///
+    /// Returns no arrays if the md module isn't loaded.
+    #[cfg(feature = "mdstat")]
+    pub fn md_arrays(&self) -> Option<&[MdArray]> {
+        self.mdstat.arrays()
+    }
+
+    /// Returns ZFS ARC size and counters as of the last call to `update`, or `None` if ZFS isn't
+    /// loaded.
+    #[cfg(feature = "zfs")]
+    pub fn zfs_arc(&self) -> Option<ZfsArc> {
+        self.zfs.arc()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of ZFS ARC reads that were hits between the last two
+    /// calls to `update`.
+    #[cfg(feature = "zfs")]
+    pub fn zfs_arc_hit_ratio(&self) -> Option<f32> {
+        self.zfs.arc_hit_ratio()
+    }
+
+    /// Returns the average connections dropped per second, because a listening TCP socket's accept
+    /// queue was full, between the last two calls to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn tcp_listen_overflow_rate(&self) -> Option<u64> {
+        self.netstat.rate("TcpExt", "ListenOverflows")
+    }
+
+    /// Returns the average connections dropped per second, for any reason, by listening TCP
+    /// sockets between the last two calls to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn tcp_listen_drop_rate(&self) -> Option<u64> {
+        self.netstat.rate("TcpExt", "ListenDrops")
+    }
+
+    /// Returns the average SYN cookies sent per second, because a SYN queue was full, between the
+    /// last two calls to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn tcp_syncookies_sent_rate(&self) -> Option<u64> {
+        self.netstat.rate("TcpExt", "SyncookiesSent")
+    }
+
+    /// Returns the average TCP packets dropped per second, because a socket's backlog was full,
+    /// between the last two calls to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn tcp_backlog_drop_rate(&self) -> Option<u64> {
+        self.netstat.rate("TcpExt", "TCPBacklogDrop")
+    }
+
+    /// Returns the average times per second TCP receive queues were pruned, due to memory
+    /// pressure, between the last two calls to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn tcp_prune_rate(&self) -> Option<u64> {
+        self.netstat.rate("TcpExt", "PruneCalled")
+    }
+
+    /// Returns any counter in `/proc/net/netstat`, e.g. `("TcpExt", "ListenOverflows")`, as of the
+    /// last call to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn netstat_counter(&self, prefix: &str, name: &str) -> Option<u64> {
+        self.netstat.counter(prefix, name)
+    }
+
+    /// Returns the average rate of change per second of any counter in `/proc/net/netstat`, e.g.
+    /// `("IpExt", "InOctets")`, between the last two calls to `update`.
+    #[cfg(feature = "netstat")]
+    pub fn netstat_rate(&self, prefix: &str, name: &str) -> Option<u64> {
+        self.netstat.rate(prefix, name)
+    }
+
+    /// Returns the memory, thread, file descriptor, and I/O usage of the current process (or the
+    /// one given to `with_process_pid`) as of the last call to `update`.
+    #[cfg(feature = "process")]
+    pub fn process(&self) -> Option<&Process> {
+        self.process.process()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of cpu, across all cores, used by the process between the
+    /// last two calls to `update`.
+    #[cfg(feature = "process")]
+    pub fn process_cpu_usage(&self) -> Option<f32> {
+        self.process.cpu_usage()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of cpu, across all cores, used by the process's threads of
+    /// each name (e.g. `tokio-runtime-w`) between the last two calls to `update`, in descending
+    /// order.
+    ///
+    /// Requires `with_process_threads`.
+    #[cfg(feature = "process")]
+    pub fn process_thread_cpu_usage(&self) -> Option<Vec<(String, f32)>> {
+        self.process.thread_cpu_usage()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of the process's open file descriptor limit
+    /// (`RLIMIT_NOFILE`) used as of the last call to `update`.
+    #[cfg(feature = "process")]
+    pub fn process_fd_usage(&self) -> Option<f32> {
+        self.process.process()?.fd_usage()
+    }
+
+    /// Returns the average bytes the process read from storage per second between the last two
+    /// calls to `update`.
+    #[cfg(feature = "process")]
+    pub fn process_read_bandwidth(&self) -> Option<u64> {
+        self.process.read_bandwidth()
+    }
+
+    /// Returns the average bytes the process wrote to storage per second between the last two
+    /// calls to `update`.
+    #[cfg(feature = "process")]
+    pub fn process_write_bandwidth(&self) -> Option<u64> {
+        self.process.write_bandwidth()
+    }
+
     /// Returns the fraction (0.0..=1.0) of ram used as of the last call to `update`.
     #[cfg(feature = "ram")]
     pub fn ram_usage(&self) -> Option<f32> {
@@ -134,17 +682,291 @@ impl SimpleServerStatus {
         self.ram.swap_usage()
     }
 
+    /// Forecasts when ram will be full, from `ram_usage` over the last `with_forecast_window`.
+    ///
+    /// Requires at least 3 calls to `update` within the window.
+    #[cfg(feature = "ram")]
+    pub fn ram_forecast(&self) -> Option<Forecast> {
+        self.ram.forecast()
+    }
+
+    /// Returns the number of raw IP sockets as of the last call to `update`.
+    #[cfg(feature = "raw")]
+    pub fn raw_sockets(&self) -> Option<usize> {
+        self.raw.sockets()
+    }
+
+    /// Returns the average TCP connections opened by this host per second between the last two
+    /// calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn tcp_active_open_rate(&self) -> Option<u64> {
+        self.snmp.tcp_active_open_rate()
+    }
+
+    /// Returns the average TCP connections accepted from other hosts per second between the last
+    /// two calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn tcp_passive_open_rate(&self) -> Option<u64> {
+        self.snmp.tcp_passive_open_rate()
+    }
+
+    /// Returns the average failed TCP connection attempts per second between the last two calls
+    /// to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn tcp_attempt_fail_rate(&self) -> Option<u64> {
+        self.snmp.tcp_attempt_fail_rate()
+    }
+
+    /// Returns the average established TCP connections reset per second between the last two
+    /// calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn tcp_established_reset_rate(&self) -> Option<u64> {
+        self.snmp.tcp_established_reset_rate()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of TCP segments sent that were retransmissions between
+    /// the last two calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn tcp_retransmit_ratio(&self) -> Option<f32> {
+        self.snmp.tcp_retransmit_ratio()
+    }
+
+    /// Returns the average erroneous TCP segments received per second between the last two calls
+    /// to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn tcp_in_error_rate(&self) -> Option<u64> {
+        self.snmp.tcp_in_error_rate()
+    }
+
+    /// Returns the average UDP datagrams that couldn't be delivered per second (for reasons other
+    /// than having no socket) between the last two calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn udp_in_error_rate(&self) -> Option<u64> {
+        self.snmp.udp_in_error_rate()
+    }
+
+    /// Returns the average UDP datagrams dropped due to a full receive buffer per second between
+    /// the last two calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn udp_receive_buffer_error_rate(&self) -> Option<u64> {
+        self.snmp.udp_receive_buffer_error_rate()
+    }
+
+    /// Returns the average UDP datagrams dropped due to a full send buffer per second between the
+    /// last two calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn udp_send_buffer_error_rate(&self) -> Option<u64> {
+        self.snmp.udp_send_buffer_error_rate()
+    }
+
+    /// Returns the average UDP datagrams received for a port with no socket per second between
+    /// the last two calls to `update`.
+    #[cfg(feature = "snmp")]
+    pub fn udp_no_port_rate(&self) -> Option<u64> {
+        self.snmp.udp_no_port_rate()
+    }
+
+    /// Returns socket totals and socket buffer memory as of the last call to `update`.
+    #[cfg(feature = "sockstat")]
+    pub fn sockstat(&self) -> Option<Sockstat> {
+        self.sockstat.sockstat()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of the TCP socket buffer memory limit used as of the last
+    /// call to `update`.
+    #[cfg(feature = "sockstat")]
+    pub fn tcp_memory_usage(&self) -> Option<f32> {
+        self.sockstat.tcp_memory_usage()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of the UDP socket buffer memory limit used as of the last
+    /// call to `update`.
+    #[cfg(feature = "sockstat")]
+    pub fn udp_memory_usage(&self) -> Option<f32> {
+        self.sockstat.udp_memory_usage()
+    }
+
+    /// Returns the number of file handles (e.g. files and sockets) open system-wide as of the last
+    /// call to `update`.
+    #[cfg(feature = "fd")]
+    pub fn file_handles(&self) -> Option<usize> {
+        self.fd.handles()
+    }
+
+    /// Returns the fraction (0.0..=1.0) of the system-wide file handle limit (`file-max`) used as
+    /// of the last call to `update`.
+    #[cfg(feature = "fd")]
+    pub fn file_handle_usage(&self) -> Option<f32> {
+        self.fd.usage()
+    }
+
+    /// Returns the system-wide file handle limit (`file-max`) as of the last call to `update`.
+    #[cfg(feature = "fd")]
+    pub fn file_handles_max(&self) -> Option<usize> {
+        self.fd.max()
+    }
+
+    /// Returns the maximum file descriptors any one process may be allowed (`nr_open`, the ceiling
+    /// for `RLIMIT_NOFILE`) as of the last call to `update`.
+    #[cfg(feature = "fd")]
+    pub fn file_handles_per_process_max(&self) -> Option<usize> {
+        self.fd.per_process_max()
+    }
+
+    /// Returns the number of inodes the kernel has allocated in memory, system-wide, as of the
+    /// last call to `update`.
+    ///
+    /// Since Linux 2.4, there is no limit on these (`inode-max`), so there is no usage ratio.
+    #[cfg(feature = "fd")]
+    pub fn inode_handles(&self) -> Option<usize> {
+        self.fd.inodes()
+    }
+
     /// Returns the number of TCP connections as of the last call to `update`.
+    ///
+    /// Includes both IPv4 and IPv6 sockets.
     #[cfg(feature = "tcp")]
     pub fn tcp_connections(&self) -> Option<usize> {
+        #[cfg(feature = "sockstat")]
+        if self.sockstat_mode {
+            return self.sockstat.tcp_connections();
+        }
         self.tcp.connections()
     }
 
+    /// Returns the number of TCP sockets in each state as of the last call to `update`.
+    ///
+    /// Unlike `tcp_connections`, this distinguishes established connections from listening
+    /// sockets, `TimeWait` remnants, and `SynRecv` half-open connections.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_states(&self) -> Option<TcpStates> {
+        self.tcp.states()
+    }
+
+    /// Returns the number of TCP sockets in each state, summed over sockets bound to any of the
+    /// given local `ports`, as of the last call to `update`.
+    ///
+    /// Useful to count connections to a particular service, e.g. `&[80, 443]`. Requires
+    /// `with_tcp_local_ports(true)`.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_local_port_states(&self, ports: &[u16]) -> Option<TcpStates> {
+        self.tcp.local_port_states(ports)
+    }
+
+    /// Returns the number of TCP sockets in each state, keyed by local port, as of the last call
+    /// to `update`.
+    ///
+    /// Outgoing connections are keyed by their (ephemeral) local port. Requires
+    /// `with_tcp_local_ports(true)`.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_local_ports(&self) -> Option<&HashMap<u16, TcpStates>> {
+        self.tcp.local_ports()
+    }
+
+    /// Returns up to `n` remote addresses with the most TCP sockets connected to them, as of the
+    /// last call to `update`, in descending order of count.
+    ///
+    /// Listening sockets are excluded, as they have no remote address. Requires
+    /// `with_tcp_peers(true)`.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_top_peers(&self, n: usize) -> Option<Vec<TcpPeer>> {
+        self.tcp.top_peers(n, 32, 128)
+    }
+
+    /// Like `tcp_top_peers`, but groups remote addresses into networks with the given prefix
+    /// lengths, e.g. `/24` for IPv4 and `/64` for IPv6. Requires `with_tcp_peers(true)`.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_top_peer_networks(
+        &self,
+        n: usize,
+        ipv4_prefix: u8,
+        ipv6_prefix: u8,
+    ) -> Option<Vec<TcpPeer>> {
+        self.tcp.top_peers(n, ipv4_prefix, ipv6_prefix)
+    }
+
+    /// Returns the total bytes queued in established TCP sockets, in each direction, as of the
+    /// last call to `update`.
+    ///
+    /// A growing send queue indicates slow clients (or a congested network).
+    #[cfg(feature = "tcp")]
+    pub fn tcp_queues(&self) -> Option<TcpQueues> {
+        self.tcp.queues()
+    }
+
+    /// Returns the number of connections waiting to be accepted, for each listening TCP socket,
+    /// as of the last call to `update`.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_accept_queues(&self) -> Option<&[TcpAcceptQueue]> {
+        self.tcp.accept_queues()
+    }
+
+    /// Returns the round trip time percentiles, congestion window, retransmits, losses, and
+    /// delivery rate of established TCP connections, as of the last call to `update`.
+    ///
+    /// Returns `None` if netlink was unavailable.
+    #[cfg(all(feature = "tcp", feature = "netlink"))]
+    pub fn tcp_health(&self) -> Option<TcpHealth> {
+        self.tcp.health()
+    }
+
     /// Returns the number of UDP sockets as of the last call to `update`.
+    ///
+    /// Includes both IPv4 and IPv6 sockets.
     #[cfg(feature = "udp")]
     pub fn udp_sockets(&self) -> Option<usize> {
+        #[cfg(feature = "sockstat")]
+        if self.sockstat_mode {
+            return self.sockstat.udp_sockets();
+        }
         self.udp.sockets()
     }
+
+    /// Returns the average UDP datagrams dropped per second between the last two calls to
+    /// `update`, e.g. because a socket's receive buffer was full.
+    #[cfg(feature = "udp")]
+    pub fn udp_drop_rate(&self) -> Option<u64> {
+        self.udp.drop_rate()
+    }
+
+    /// Returns the total UDP datagrams dropped by sockets on each local port, over the lifetime
+    /// of the sockets, as of the last call to `update`.
+    #[cfg(feature = "udp")]
+    pub fn udp_local_port_drops(&self) -> Option<&HashMap<u16, u64>> {
+        self.udp.local_port_drops()
+    }
+
+    /// Returns the total bytes queued in UDP sockets' receive buffers, waiting to be read by the
+    /// application, as of the last call to `update`.
+    ///
+    /// Includes the kernel's per-datagram overhead, so may exceed the size of the payloads.
+    #[cfg(feature = "udp")]
+    pub fn udp_receive_queue(&self) -> Option<u64> {
+        self.udp.receive_queue()
+    }
+
+    /// Returns the number of UDP-Lite sockets as of the last call to `update`.
+    #[cfg(feature = "udplite")]
+    pub fn udplite_sockets(&self) -> Option<usize> {
+        self.udplite.sockets()
+    }
+
+    /// Returns the number of Unix domain sockets of each type and state as of the last call to
+    /// `update`.
+    #[cfg(feature = "unix")]
+    pub fn unix_sockets(&self) -> Option<UnixSockets> {
+        self.unix.sockets()
+    }
+
+    /// Returns the processes with TCP or UDP sockets open, in descending order of socket count.
+    ///
+    /// Unlike other methods, this makes a new measurement instead of using the result of `update`.
+    /// It scans the file descriptors of every process, so is much more expensive than `update`,
+    /// and requires permission to see other users' processes.
+    #[cfg(any(feature = "tcp", feature = "udp"))]
+    pub fn socket_owners(&self) -> io::Result<Vec<SocketOwner>> {
+        owner::sample()
+    }
 }
 
 /// Parse the next u64 from a string of tokens. Will return error if it doesn't exist or could not
@@ -172,6 +994,76 @@ fn next_or_zero(tokens: &mut SplitAsciiWhitespace) -> io::Result<u64> {
     Ok(token.parse::<u64>().unwrap_or(0))
 }
 
+/// Calls `f` with each line of each file in `paths`, reusing one allocation, as some files (e.g.
+/// `/proc/net/tcp`) may have many lines.
+///
+/// Lines that aren't valid UTF-8 (e.g. socket paths in `/proc/net/unix`, which are printed as raw
+/// bytes) are decoded lossily, so callers should only rely on ASCII columns.
+///
+/// Missing IPv6 variants (e.g. `/proc/net/tcp6`) are skipped, as IPv6 may be disabled.
+#[allow(unused)]
+fn for_each_line(paths: &[&str], mut f: impl FnMut(&str)) -> io::Result<()> {
+    let mut line = Vec::new();
+    for path in paths {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && path.ends_with('6') => continue,
+            Err(e) => return Err(e),
+        };
+        let mut reader = BufReader::new(file);
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            f(&String::from_utf8_lossy(&line));
+        }
+    }
+    Ok(())
+}
+
+/// Reads a file containing a single number, like `/proc/sys/fs/file-max`.
+#[allow(unused)]
+fn read_usize(path: &str) -> io::Result<usize> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut tokens = contents.split_ascii_whitespace();
+    Ok(next(&mut tokens)? as usize)
+}
+
+/// Parses files like `/proc/net/snmp`, in which each line of field names is followed by a line of
+/// values, both starting with a prefix like `Tcp:`. Calls `f` with each prefix (e.g. `Tcp`), field
+/// name (e.g. `ActiveOpens`), and value.
+///
+/// Values that aren't a u64 (e.g. `-1` for `Tcp` `MaxConn`) are skipped.
+#[allow(unused)]
+fn for_each_paired_field(path: &str, mut f: impl FnMut(&str, &str, u64)) -> io::Result<()> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut lines = contents.lines();
+    while let (Some(names), Some(values)) = (lines.next(), lines.next()) {
+        let mut names = names.split_ascii_whitespace();
+        let mut values = values.split_ascii_whitespace();
+        let (Some(prefix), Some(value_prefix)) = (names.next(), values.next()) else {
+            continue;
+        };
+        if prefix != value_prefix {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} mismatched prefixes '{}' and '{}'",
+                    path, prefix, value_prefix
+                ),
+            ));
+        }
+        let prefix = prefix.trim_end_matches(':');
+        for (name, value) in names.zip(values) {
+            if let Ok(value) = value.parse::<u64>() {
+                f(prefix, name, value);
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Outputs between 0 and 1 (None in the case of dividing by 0).
 #[allow(unused)]
 fn sanitize_division(numerator: u64, denominator: u64) -> Option<f32> {
@@ -187,6 +1079,32 @@ fn sanitize_division(numerator: u64, denominator: u64) -> Option<f32> {
     }
 }
 
+/// Parses an address like `0100007F:0016` (`127.0.0.1:22`).
+///
+/// The kernel prints the IP address as 32-bit words in host byte order (little-endian on most
+/// servers), but the port in the usual order. IPv4-mapped IPv6 addresses are converted to IPv4.
+#[allow(unused)]
+fn parse_address(address: &str) -> Option<SocketAddr> {
+    let (ip, port) = address.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let word = |i: usize| -> Option<[u8; 4]> {
+        let word = u32::from_str_radix(ip.get(i * 8..(i + 1) * 8)?, 16).ok()?;
+        Some(word.to_ne_bytes())
+    };
+    let ip = match ip.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(word(0)?)),
+        32 => {
+            let mut octets = [0u8; 16];
+            for (i, chunk) in octets.chunks_exact_mut(4).enumerate() {
+                chunk.copy_from_slice(&word(i)?);
+            }
+            Ipv6Addr::from(octets).to_canonical()
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
 #[allow(unused)]
 fn unix_millis() -> u64 {
     SystemTime::now()
@@ -201,6 +1119,6 @@ macro_rules! delta {
     ($old: expr, $new: expr, $numerator: ident, $denominator: ident) => {{
         let numerator = $new.$numerator().saturating_sub($old.$numerator());
         let denominator = $new.$denominator().saturating_sub($old.$denominator());
-        crate::sanitize_division(numerator, denominator)
+        $crate::sanitize_division(numerator, denominator)
     }};
 }
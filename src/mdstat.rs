@@ -0,0 +1,273 @@
+use std::time::Duration;
+use std::{fs, io};
+
+#[derive(Debug, Default)]
+pub struct MdstatStatus {
+    arrays: Option<Vec<MdArray>>,
+}
+
+impl MdstatStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.arrays = None;
+        self.arrays = Some(MdArray::sample()?);
+        Ok(())
+    }
+
+    pub fn arrays(&self) -> Option<&[MdArray]> {
+        self.arrays.as_deref()
+    }
+}
+
+/// Software RAID array, from `/proc/mdstat`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdArray {
+    /// e.g. `md0`
+    pub name: String,
+    /// Whether the array is running. Inactive arrays have no level.
+    pub active: bool,
+    /// e.g. `raid1` or `raid5`
+    pub level: Option<String>,
+    pub members: Vec<MdMember>,
+    /// Number of devices the array should have.
+    pub devices: usize,
+    /// Number of devices the array has working.
+    pub working_devices: usize,
+    /// Resync, recovery, check, or reshape in progress (or pending).
+    pub sync: Option<MdSync>,
+}
+
+/// Member device of a software RAID array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdMember {
+    /// e.g. `sda1`
+    pub device: String,
+    pub failed: bool,
+    pub spare: bool,
+}
+
+/// Progress of a software RAID array's resync, recovery, check, or reshape.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MdSync {
+    /// e.g. `resync`, `recovery`, `check`, or `reshape`
+    pub action: String,
+    /// Fraction (0.0..=1.0) completed.
+    pub progress: f32,
+    /// Estimated time remaining, or `None` if not started (e.g. delayed).
+    pub eta: Option<Duration>,
+}
+
+impl MdArray {
+    fn sample() -> io::Result<Vec<Self>> {
+        match fs::read_to_string("/proc/mdstat") {
+            Ok(mdstat) => Ok(Self::parse(&mdstat)),
+            // The md module isn't loaded, so there can't be any arrays.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse(mdstat: &str) -> Vec<Self> {
+        let mut ret = Vec::<Self>::new();
+        for line in mdstat.lines() {
+            if !line.starts_with(char::is_whitespace) {
+                // e.g. "md0 : active raid5 sdc1[3](S) sdb1[1] sda1[0] sdd1[4](F)"
+                let mut tokens = line.split_ascii_whitespace();
+                let (Some(name), Some(":"), Some(state)) =
+                    (tokens.next(), tokens.next(), tokens.next())
+                else {
+                    // e.g. "Personalities : [raid1]" or "unused devices: <none>"
+                    continue;
+                };
+                if name == "Personalities" {
+                    continue;
+                }
+                let active = state == "active";
+                // e.g. "(auto-read-only)"
+                let mut tokens = tokens.skip_while(|token| token.starts_with('('));
+                let level = if active {
+                    tokens.next().map(str::to_owned)
+                } else {
+                    None
+                };
+                ret.push(Self {
+                    name: name.to_owned(),
+                    active,
+                    level,
+                    members: tokens.filter_map(MdMember::parse).collect(),
+                    devices: 0,
+                    working_devices: 0,
+                    sync: None,
+                });
+                continue;
+            }
+            let Some(array) = ret.last_mut() else {
+                continue;
+            };
+            for token in line.split_ascii_whitespace() {
+                // e.g. "[3/2]"
+                let Some((devices, working_devices)) = token
+                    .strip_prefix('[')
+                    .and_then(|token| token.strip_suffix(']'))
+                    .and_then(|token| token.split_once('/'))
+                else {
+                    continue;
+                };
+                if let (Ok(devices), Ok(working_devices)) =
+                    (devices.parse(), working_devices.parse())
+                {
+                    array.devices = devices;
+                    array.working_devices = working_devices;
+                }
+            }
+            if let Some(sync) = MdSync::parse(line) {
+                array.sync = Some(sync);
+            }
+        }
+        ret
+    }
+
+    /// Returns whether fewer devices are working than the array should have.
+    pub fn degraded(&self) -> bool {
+        self.working_devices < self.devices
+    }
+}
+
+impl MdMember {
+    /// Parses e.g. `sdd1[4](F)`.
+    fn parse(token: &str) -> Option<Self> {
+        let (device, flags) = token.split_once('[')?;
+        let (_index, flags) = flags.split_once(']')?;
+        Some(Self {
+            device: device.to_owned(),
+            failed: flags.contains("(F)"),
+            spare: flags.contains("(S)"),
+        })
+    }
+}
+
+impl MdSync {
+    /// Parses e.g. `[=>....]  recovery =  8.5% (1234/5678) finish=12.3min speed=12345K/sec` or
+    /// `resync=DELAYED`.
+    fn parse(line: &str) -> Option<Self> {
+        const ACTIONS: &[&str] = &["resync", "recovery", "check", "repair", "reshape"];
+
+        let mut tokens = line.split_ascii_whitespace();
+        while let Some(token) = tokens.next() {
+            if let Some((action, _pending)) = token.split_once('=') {
+                if ACTIONS.contains(&action) {
+                    return Some(Self {
+                        action: action.to_owned(),
+                        progress: 0.0,
+                        eta: None,
+                    });
+                }
+            }
+            if !ACTIONS.contains(&token) {
+                continue;
+            }
+            let progress = tokens
+                .nth(1)?
+                .strip_suffix('%')?
+                .parse::<f32>()
+                .ok()?
+                .clamp(0.0, 100.0)
+                / 100.0;
+            let eta = tokens
+                .find_map(|token| token.strip_prefix("finish="))
+                .and_then(|finish| finish.strip_suffix("min"))
+                .and_then(|minutes| minutes.parse::<f64>().ok())
+                .and_then(|minutes| Duration::try_from_secs_f64(minutes * 60.0).ok());
+            return Some(Self {
+                action: token.to_owned(),
+                progress,
+                eta,
+            });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mdstat::{MdArray, MdMember, MdSync};
+    use crate::SimpleServerStatus;
+
+    #[test]
+    fn mdstat() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.md_arrays(), None);
+
+        status.update().unwrap();
+
+        let arrays = status.md_arrays().unwrap();
+        println!("md_arrays: {:?}", arrays);
+        for array in arrays {
+            assert!(array.working_devices <= array.devices);
+        }
+    }
+
+    #[test]
+    fn mdstat_parse() {
+        let arrays = MdArray::parse(
+            "Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid1 sdb2[1] sda2[0]
+      1953381376 blocks super 1.2 [2/2] [UU]
+      bitmap: 2/15 pages [8KB], 65536KB chunk
+
+md0 : active raid5 sdc1[3](S) sdb1[1] sda1[0] sdd1[4](F)
+      3906762752 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [UU_]
+      [=>...................]  recovery =  8.5% (166016384/1953381376) finish=147.3min speed=202176K/sec
+
+md2 : active (auto-read-only) raid1 sde1[0] sdf1[1]
+      1048512 blocks [2/2] [UU]
+        resync=PENDING
+
+md3 : inactive sdg1[0](S)
+      1048512 blocks
+
+unused devices: <none>
+",
+        );
+        assert_eq!(arrays.len(), 4);
+
+        assert_eq!(arrays[0].name, "md1");
+        assert_eq!(arrays[0].level.as_deref(), Some("raid1"));
+        assert_eq!(arrays[0].members.len(), 2);
+        assert!(!arrays[0].degraded());
+        assert_eq!(arrays[0].sync, None);
+
+        assert_eq!(arrays[1].level.as_deref(), Some("raid5"));
+        assert_eq!(
+            arrays[1].members[0],
+            MdMember {
+                device: "sdc1".to_owned(),
+                failed: false,
+                spare: true,
+            }
+        );
+        assert!(arrays[1].members[3].failed);
+        assert_eq!((arrays[1].devices, arrays[1].working_devices), (3, 2));
+        assert!(arrays[1].degraded());
+        let sync = arrays[1].sync.as_ref().unwrap();
+        assert_eq!(sync.action, "recovery");
+        assert!((sync.progress - 0.085).abs() < 0.0001);
+        let eta = sync.eta.unwrap().as_secs_f64();
+        assert!((eta - 147.3 * 60.0).abs() < 0.01);
+
+        assert_eq!(arrays[2].level.as_deref(), Some("raid1"));
+        assert_eq!(
+            arrays[2].sync,
+            Some(MdSync {
+                action: "resync".to_owned(),
+                progress: 0.0,
+                eta: None,
+            })
+        );
+
+        assert!(!arrays[3].active);
+        assert_eq!(arrays[3].level, None);
+        assert!(arrays[3].members[0].spare);
+        assert!(!arrays[3].degraded());
+    }
+}
@@ -62,8 +62,10 @@ impl NetCounters {
     fn sample() -> io::Result<Self> {
         let proc_stat = File::open("/proc/net/dev")?;
         let reader = BufReader::new(proc_stat);
-        let mut ret = Self::default();
-        ret.unix_millis = unix_millis();
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
         for line in reader.lines().skip(2) {
             let line = line?;
             let mut tokens = line.split_ascii_whitespace();
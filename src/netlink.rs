@@ -0,0 +1,242 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const NLMSG_HEADER_LEN: usize = 16;
+const INET_DIAG_REQ_V2_LEN: usize = 56;
+const INET_DIAG_MSG_LEN: usize = 72;
+
+/// Attribute containing `struct tcp_info`.
+#[cfg_attr(not(feature = "tcp"), allow(unused))]
+pub const INET_DIAG_INFO: u16 = 2;
+/// Attribute containing `SK_MEMINFO_*` counters.
+#[cfg_attr(not(feature = "udp"), allow(unused))]
+pub const INET_DIAG_SKMEMINFO: u16 = 7;
+
+/// Every state in `TcpState::ALL` (1 through 12), so that netlink reports the same sockets as
+/// `/proc/net/tcp`. Not `u32::MAX`, which since Linux 6.8 includes `TCP_BOUND_INACTIVE` (13),
+/// i.e. sockets that are bound but neither connected nor listening.
+pub const ALL_STATES: u32 = ((1 << 13) - 1) & !1;
+
+/// Returns the `idiag_states` bitmask matching the given states, numbered as in
+/// `include/net/tcp_states.h` (e.g. 10 for `TCP_LISTEN`).
+pub fn states_mask(states: impl IntoIterator<Item = u8>) -> u32 {
+    states.into_iter().fold(0, |mask, state| {
+        mask | 1u32.checked_shl(state as u32).unwrap_or(0)
+    })
+}
+
+/// A socket reported by the kernel, i.e. `struct inet_diag_msg` and its attributes.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "tcp"), allow(unused))]
+pub struct InetDiagMsg<'a> {
+    pub state: u8,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub rqueue: u32,
+    pub wqueue: u32,
+    pub inode: u32,
+    attributes: &'a [u8],
+}
+
+impl<'a> InetDiagMsg<'a> {
+    /// Returns the payload of the first attribute of type `kind`, e.g. `INET_DIAG_INFO`.
+    ///
+    /// The attribute must have been requested via `extensions`.
+    pub fn attribute(&self, kind: u16) -> Option<&'a [u8]> {
+        let mut attributes = self.attributes;
+        // struct rtattr
+        while let (Some(len), Some(attribute_kind)) =
+            (read_u16(attributes, 0), read_u16(attributes, 2))
+        {
+            let len = len as usize;
+            if len < 4 || len > attributes.len() {
+                break;
+            }
+            if attribute_kind == kind {
+                return Some(&attributes[4..len]);
+            }
+            attributes = attributes.get(align(len)..).unwrap_or_default();
+        }
+        None
+    }
+
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        let family = *bytes.first()?;
+        let address = |offset: usize| -> Option<IpAddr> {
+            let octets: [u8; 16] = bytes.get(offset..offset + 16)?.try_into().ok()?;
+            Some(match family as i32 {
+                libc::AF_INET => {
+                    IpAddr::V4(Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]))
+                }
+                _ => Ipv6Addr::from(octets).to_canonical(),
+            })
+        };
+        let port = |offset: usize| -> Option<u16> {
+            Some(u16::from_be_bytes(
+                bytes.get(offset..offset + 2)?.try_into().ok()?,
+            ))
+        };
+        Some(Self {
+            state: *bytes.get(1)?,
+            local: SocketAddr::new(address(8)?, port(4)?),
+            remote: SocketAddr::new(address(24)?, port(6)?),
+            rqueue: read_u32(bytes, 56)?,
+            wqueue: read_u32(bytes, 60)?,
+            inode: read_u32(bytes, 68)?,
+            attributes: bytes.get(INET_DIAG_MSG_LEN..)?,
+        })
+    }
+}
+
+/// Dumps every socket of the given `protocol` (e.g. `IPPROTO_TCP`), IPv4 and IPv6, whose state
+/// is in the `states` bitmask. `extensions` is a bitmask of `INET_DIAG_*` attributes to request.
+/// See `man 7 sock_diag`.
+///
+/// The kernel does the filtering, so this is much faster than parsing `/proc/net/tcp`.
+pub fn sock_diag(
+    protocol: u8,
+    states: u32,
+    extensions: u8,
+    mut f: impl FnMut(&InetDiagMsg),
+) -> io::Result<()> {
+    // SAFETY: Plain syscall, the result is checked.
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `fd` was just opened and isn't owned by anything else.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut buffer = vec![0u8; 64 * 1024];
+    for family in [libc::AF_INET as u8, libc::AF_INET6 as u8] {
+        send_request(&fd, family, protocol, states, extensions)?;
+        receive_dump(&fd, &mut buffer, &mut f)?;
+    }
+    Ok(())
+}
+
+fn send_request(
+    fd: &OwnedFd,
+    family: u8,
+    protocol: u8,
+    states: u32,
+    extensions: u8,
+) -> io::Result<()> {
+    let len = NLMSG_HEADER_LEN + INET_DIAG_REQ_V2_LEN;
+    let mut request = Vec::with_capacity(len);
+    // struct nlmsghdr
+    request.extend_from_slice(&(len as u32).to_ne_bytes());
+    request.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    request.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    request.extend_from_slice(&1u32.to_ne_bytes());
+    request.extend_from_slice(&0u32.to_ne_bytes());
+    // struct inet_diag_req_v2
+    request.extend_from_slice(&[family, protocol, extensions, 0]);
+    request.extend_from_slice(&states.to_ne_bytes());
+    // struct inet_diag_sockid (no filter)
+    request.resize(len, 0);
+
+    // SAFETY: `request` is valid for `request.len()` bytes.
+    let sent = unsafe {
+        libc::send(
+            fd.as_raw_fd(),
+            request.as_ptr() as *const libc::c_void,
+            request.len(),
+            0,
+        )
+    };
+    if sent < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+fn receive_dump(
+    fd: &OwnedFd,
+    buffer: &mut [u8],
+    f: &mut impl FnMut(&InetDiagMsg),
+) -> io::Result<()> {
+    loop {
+        // SAFETY: `buffer` is valid for `buffer.len()` bytes.
+        let received = unsafe {
+            libc::recv(
+                fd.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error);
+        }
+
+        let mut messages = &buffer[..received as usize];
+        while messages.len() >= NLMSG_HEADER_LEN {
+            let len = read_u32(messages, 0).unwrap_or_default() as usize;
+            let kind = read_u16(messages, 4).unwrap_or_default();
+            if len < NLMSG_HEADER_LEN || len > messages.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "netlink message truncated",
+                ));
+            }
+            let payload = &messages[NLMSG_HEADER_LEN..len];
+            match kind as i32 {
+                libc::NLMSG_DONE => return Ok(()),
+                libc::NLMSG_ERROR => {
+                    let errno = read_u32(payload, 0).unwrap_or(0) as i32;
+                    if errno != 0 {
+                        return Err(io::Error::from_raw_os_error(-errno));
+                    }
+                }
+                _ if kind == SOCK_DIAG_BY_FAMILY => {
+                    if let Some(msg) = InetDiagMsg::parse(payload) {
+                        f(&msg);
+                    }
+                }
+                _ => {}
+            }
+            messages = messages.get(align(len)..).unwrap_or_default();
+        }
+    }
+}
+
+/// Reads a native-endian `u16` at `offset`, if in bounds.
+pub fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(
+        bytes.get(offset..offset.checked_add(2)?)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a native-endian `u32` at `offset`, if in bounds.
+pub fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(
+        bytes.get(offset..offset.checked_add(4)?)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a native-endian `u64` at `offset`, if in bounds.
+#[cfg_attr(not(feature = "tcp"), allow(unused))]
+pub fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_ne_bytes(
+        bytes.get(offset..offset.checked_add(8)?)?.try_into().ok()?,
+    ))
+}
+
+/// Netlink messages and attributes are aligned to 4 bytes.
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
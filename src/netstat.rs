@@ -0,0 +1,114 @@
+use crate::{for_each_paired_field, unix_millis};
+use std::collections::HashMap;
+use std::{io, mem};
+
+#[derive(Debug, Default)]
+pub struct NetstatStatus {
+    old: NetstatCounters,
+    new: NetstatCounters,
+}
+
+#[derive(Debug, Default)]
+struct NetstatCounters {
+    unix_millis: u64,
+    /// e.g. `counters["TcpExt"]["ListenOverflows"]`
+    counters: HashMap<String, HashMap<String, u64>>,
+}
+
+impl NetstatStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.old = mem::take(&mut self.new);
+        self.new = NetstatCounters::sample()?;
+        Ok(())
+    }
+
+    pub fn counter(&self, prefix: &str, name: &str) -> Option<u64> {
+        self.new.get(prefix, name)
+    }
+
+    /// Calculates rate of change per second.
+    pub fn rate(&self, prefix: &str, name: &str) -> Option<u64> {
+        let count = self
+            .new
+            .get(prefix, name)?
+            .saturating_sub(self.old.get(prefix, name).unwrap_or(0));
+        let millis = self.new.unix_millis.saturating_sub(self.old.unix_millis);
+        count.saturating_mul(1000).checked_div(millis)
+    }
+}
+
+impl NetstatCounters {
+    fn sample() -> io::Result<Self> {
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
+        for_each_paired_field("/proc/net/netstat", |prefix, name, value| {
+            let counters = match ret.counters.get_mut(prefix) {
+                Some(counters) => counters,
+                None => ret.counters.entry(prefix.to_owned()).or_default(),
+            };
+            counters.insert(name.to_owned(), value);
+        })?;
+        Ok(ret)
+    }
+
+    fn get(&self, prefix: &str, name: &str) -> Option<u64> {
+        self.counters.get(prefix)?.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::netstat::NetstatCounters;
+    use crate::SimpleServerStatus;
+    use std::time::Duration;
+
+    #[test]
+    fn netstat() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.tcp_listen_overflow_rate(), None);
+        assert_eq!(status.netstat_counter("TcpExt", "ListenOverflows"), None);
+
+        status.update().unwrap();
+
+        assert!(status
+            .netstat_counter("TcpExt", "ListenOverflows")
+            .is_some());
+        assert!(status.netstat_counter("IpExt", "InOctets").is_some());
+        assert_eq!(status.netstat_counter("TcpExt", "NoSuchCounter"), None);
+
+        std::thread::sleep(Duration::from_millis(100));
+        status.update().unwrap();
+
+        println!(
+            "tcp_listen_overflow_rate: {}",
+            status.tcp_listen_overflow_rate().unwrap()
+        );
+        println!(
+            "tcp_listen_drop_rate: {}",
+            status.tcp_listen_drop_rate().unwrap()
+        );
+        println!(
+            "tcp_syncookies_sent_rate: {}",
+            status.tcp_syncookies_sent_rate().unwrap()
+        );
+        println!(
+            "tcp_backlog_drop_rate: {}",
+            status.tcp_backlog_drop_rate().unwrap()
+        );
+        println!("tcp_prune_rate: {}", status.tcp_prune_rate().unwrap());
+        println!(
+            "netstat_rate IpExt InOctets: {}",
+            status.netstat_rate("IpExt", "InOctets").unwrap()
+        );
+    }
+
+    #[test]
+    fn netstat_counters() {
+        let counters = NetstatCounters::sample().unwrap();
+        println!("netstat_counters: {:?}", counters);
+        assert!(counters.counters.contains_key("TcpExt"));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::for_each_line;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+/// A process and the number of sockets it has open.
+///
+/// Sockets shared between processes (e.g. a listening socket inherited by worker processes) are
+/// counted for each of them. Sockets without an owner (e.g. in the `TimeWait` state) aren't
+/// counted at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketOwner {
+    pub pid: u32,
+    /// Command name from `/proc/<pid>/comm`, e.g. `nginx`.
+    pub command: String,
+    pub tcp_sockets: usize,
+    pub udp_sockets: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+/// Attributes every TCP and UDP socket to the processes that have it open, in descending order of
+/// socket count.
+pub fn sample() -> io::Result<Vec<SocketOwner>> {
+    let inodes = socket_inodes()?;
+    let mut ret = Vec::new();
+    for entry in fs::read_dir("/proc")? {
+        let entry = entry?;
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        // The process may have exited, or belong to another user.
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let mut owner = SocketOwner {
+            pid,
+            command: String::new(),
+            tcp_sockets: 0,
+            udp_sockets: 0,
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            // e.g. "socket:[12345]"
+            let Some(inode) = target
+                .to_str()
+                .and_then(|target| target.strip_prefix("socket:["))
+                .and_then(|target| target.strip_suffix(']'))
+                .and_then(|inode| inode.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            match inodes.get(&inode) {
+                Some(Protocol::Tcp) => owner.tcp_sockets = owner.tcp_sockets.saturating_add(1),
+                Some(Protocol::Udp) => owner.udp_sockets = owner.udp_sockets.saturating_add(1),
+                None => {}
+            }
+        }
+        if owner.tcp_sockets == 0 && owner.udp_sockets == 0 {
+            continue;
+        }
+        owner.command = fs::read_to_string(entry.path().join("comm"))
+            .map(|comm| comm.trim_end().to_owned())
+            .unwrap_or_default();
+        ret.push(owner);
+    }
+    ret.sort_unstable_by(|a, b| {
+        (b.tcp_sockets.saturating_add(b.udp_sockets))
+            .cmp(&a.tcp_sockets.saturating_add(a.udp_sockets))
+            .then(a.pid.cmp(&b.pid))
+    });
+    Ok(ret)
+}
+
+/// Returns the protocol of every TCP and UDP socket, keyed by inode.
+fn socket_inodes() -> io::Result<HashMap<u64, Protocol>> {
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    if let Ok(ret) = socket_inodes_netlink() {
+        return Ok(ret);
+    }
+    socket_inodes_proc()
+}
+
+#[cfg(all(feature = "netlink", target_os = "linux"))]
+fn socket_inodes_netlink() -> io::Result<HashMap<u64, Protocol>> {
+    let mut ret = HashMap::new();
+    for (protocol, ip_protocol) in [
+        (Protocol::Tcp, libc::IPPROTO_TCP),
+        (Protocol::Udp, libc::IPPROTO_UDP),
+    ] {
+        crate::netlink::sock_diag(ip_protocol as u8, crate::netlink::ALL_STATES, 0, |msg| {
+            if msg.inode != 0 {
+                ret.insert(msg.inode as u64, protocol);
+            }
+        })?;
+    }
+    Ok(ret)
+}
+
+fn socket_inodes_proc() -> io::Result<HashMap<u64, Protocol>> {
+    let mut ret = HashMap::new();
+    for (protocol, paths) in [
+        (Protocol::Tcp, ["/proc/net/tcp", "/proc/net/tcp6"]),
+        (Protocol::Udp, ["/proc/net/udp", "/proc/net/udp6"]),
+    ] {
+        for_each_line(&paths, |line| {
+            // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout
+            // inode ...
+            let inode = line
+                .split_ascii_whitespace()
+                .nth(9)
+                .and_then(|inode| inode.parse::<u64>().ok());
+            if let Some(inode) = inode.filter(|&inode| inode != 0) {
+                ret.insert(inode, protocol);
+            }
+        })?;
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+    use std::net::{TcpListener, UdpSocket};
+
+    #[test]
+    fn socket_owners() {
+        let _listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let status = SimpleServerStatus::default();
+        let owners = status.socket_owners().unwrap();
+        for owner in owners.iter().take(10) {
+            println!("socket_owners: {:?}", owner);
+        }
+
+        let owner = owners
+            .iter()
+            .find(|owner| owner.pid == std::process::id())
+            .unwrap();
+        assert!(!owner.command.is_empty());
+        assert!(owner.tcp_sockets >= 1);
+        assert!(owner.udp_sockets >= 1);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::{delta, next, sanitize_division, unix_millis};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::{io, mem};
+
+#[derive(Debug, Default)]
+pub struct ProcessStatus {
+    /// Process to measure, or `None` for the current process.
+    pub pid: Option<u32>,
+    /// Whether to measure each thread of the process.
+    pub threads: bool,
+    process: Option<Process>,
+    old: ProcessCounters,
+    new: ProcessCounters,
+}
+
+#[derive(Debug, Default)]
+struct ProcessCounters {
+    unix_millis: u64,
+    /// Clock ticks spent by the process, in user and kernel mode.
+    cpu_ticks: u64,
+    /// Clock ticks elapsed, summed across all CPUs.
+    total_ticks: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+    /// Name and clock ticks spent of each thread, by thread id.
+    threads: HashMap<u32, (String, u64)>,
+}
+
+/// Resource usage of a process, as of the last call to `update`.
+///
+/// Fields that require permission to inspect the process (e.g. if it belongs to another user)
+/// are `None` without it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    /// Bytes of memory resident in RAM, including memory shared with other processes.
+    pub rss: u64,
+    /// Bytes of memory resident in RAM, with memory shared between `n` processes counted as `1/n`
+    /// (the proportional set size). Requires Linux 4.14.
+    pub pss: Option<u64>,
+    pub threads: usize,
+    /// Open file descriptors.
+    pub open_fds: Option<usize>,
+    /// Limit on open file descriptors (the soft `RLIMIT_NOFILE`), or `None` if unlimited.
+    pub max_fds: Option<usize>,
+    /// Bytes read from storage, since the process started.
+    pub read_bytes: Option<u64>,
+    /// Bytes written to storage, since the process started.
+    pub write_bytes: Option<u64>,
+}
+
+impl ProcessStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.process = None;
+        self.old = mem::take(&mut self.new);
+        let dir = match self.pid {
+            Some(pid) => format!("/proc/{pid}"),
+            None => "/proc/self".to_owned(),
+        };
+        let (process, counters) = Process::sample(&dir, self.threads)?;
+        self.process = Some(process);
+        self.new = counters;
+        Ok(())
+    }
+
+    pub fn process(&self) -> Option<&Process> {
+        self.process.as_ref()
+    }
+
+    pub fn cpu_usage(&self) -> Option<f32> {
+        delta!(self.old, self.new, cpu_ticks, total_ticks)
+    }
+
+    /// Returns the fraction (0.0..=1.0) of cpu, across all cores, used by threads of each name,
+    /// in descending order.
+    pub fn thread_cpu_usage(&self) -> Option<Vec<(String, f32)>> {
+        if !self.threads || self.process.is_none() {
+            return None;
+        }
+        let total_ticks = self.new.total_ticks.saturating_sub(self.old.total_ticks);
+        let mut ticks = HashMap::<&str, u64>::new();
+        for (tid, (name, new)) in &self.new.threads {
+            // Threads started since the last update spent all their ticks since then.
+            let old = match self.old.threads.get(tid) {
+                Some((_, old)) => *old,
+                None => 0,
+            };
+            let sum = ticks.entry(name).or_default();
+            *sum = sum.saturating_add(new.saturating_sub(old));
+        }
+        let mut ret = ticks
+            .into_iter()
+            .map(|(name, ticks)| {
+                let usage = sanitize_division(ticks, total_ticks).unwrap_or(0.0);
+                (name.to_owned(), usage)
+            })
+            .collect::<Vec<_>>();
+        ret.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Some(ret)
+    }
+
+    /// Returns `None` if `/proc/<pid>/io` wasn't readable (e.g. it belongs to another user).
+    pub fn read_bandwidth(&self) -> Option<u64> {
+        let process = self.process.as_ref()?;
+        process
+            .read_bytes
+            .and_then(|_| self.rate(|counters| counters.read_bytes))
+    }
+
+    /// Returns `None` if `/proc/<pid>/io` wasn't readable (e.g. it belongs to another user).
+    pub fn write_bandwidth(&self) -> Option<u64> {
+        let process = self.process.as_ref()?;
+        process
+            .write_bytes
+            .and_then(|_| self.rate(|counters| counters.write_bytes))
+    }
+
+    /// Calculates rate of change per second.
+    fn rate(&self, counter: impl Fn(&ProcessCounters) -> u64) -> Option<u64> {
+        let count = counter(&self.new).saturating_sub(counter(&self.old));
+        let millis = self.new.unix_millis.saturating_sub(self.old.unix_millis);
+        count.saturating_mul(1000).checked_div(millis)
+    }
+}
+
+impl ProcessCounters {
+    fn cpu_ticks(&self) -> u64 {
+        self.cpu_ticks
+    }
+
+    fn total_ticks(&self) -> u64 {
+        self.total_ticks
+    }
+}
+
+impl Process {
+    fn sample(dir: &str, measure_threads: bool) -> io::Result<(Self, ProcessCounters)> {
+        let unix_millis = unix_millis();
+        let (pid, cpu_ticks) = read_stat(&format!("{dir}/stat"))?;
+        let total_ticks = read_total_ticks()?;
+
+        let mut rss = 0;
+        let mut threads = 0;
+        for_each_field(&format!("{dir}/status"), |name, value| match name {
+            "VmRSS" => rss = parse_kb(value).unwrap_or(0),
+            "Threads" => threads = value.trim().parse().unwrap_or(0),
+            _ => {}
+        })?;
+
+        let mut pss = None;
+        let _ = for_each_field(&format!("{dir}/smaps_rollup"), |name, value| {
+            if name == "Pss" {
+                pss = parse_kb(value);
+            }
+        });
+
+        let (mut read_bytes, mut write_bytes) = (None, None);
+        let _ = for_each_field(&format!("{dir}/io"), |name, value| match name {
+            "read_bytes" => read_bytes = value.trim().parse().ok(),
+            "write_bytes" => write_bytes = value.trim().parse().ok(),
+            _ => {}
+        });
+
+        // Don't count the directory's own fd when listing our own fds.
+        let own_fd = usize::from(pid == std::process::id());
+        let open_fds = fs::read_dir(format!("{dir}/fd"))
+            .ok()
+            .map(|entries| entries.count().saturating_sub(own_fd));
+
+        Ok((
+            Self {
+                pid,
+                rss,
+                pss,
+                threads,
+                open_fds,
+                max_fds: read_max_fds(&format!("{dir}/limits")).ok().flatten(),
+                read_bytes,
+                write_bytes,
+            },
+            ProcessCounters {
+                unix_millis,
+                cpu_ticks,
+                total_ticks,
+                read_bytes: read_bytes.unwrap_or(0),
+                write_bytes: write_bytes.unwrap_or(0),
+                threads: if measure_threads {
+                    sample_threads(dir)?
+                } else {
+                    HashMap::new()
+                },
+            },
+        ))
+    }
+
+    /// Returns the fraction (0.0..=1.0) of the file descriptor limit used.
+    pub fn fd_usage(&self) -> Option<f32> {
+        sanitize_division(self.open_fds? as u64, self.max_fds? as u64)
+    }
+}
+
+/// Reads the name and clock ticks spent of each thread, by thread id.
+fn sample_threads(dir: &str) -> io::Result<HashMap<u32, (String, u64)>> {
+    let mut ret = HashMap::new();
+    for entry in fs::read_dir(format!("{dir}/task"))? {
+        let task = entry?.path();
+        // Skip threads that exited in the meantime.
+        let Ok((tid, ticks)) = read_stat(&format!("{}/stat", task.display())) else {
+            continue;
+        };
+        let Ok(name) = fs::read_to_string(task.join("comm")) else {
+            continue;
+        };
+        ret.insert(tid, (name.trim_end().to_owned(), ticks));
+    }
+    Ok(ret)
+}
+
+/// Reads the pid, and clock ticks spent in user and kernel mode, from a `stat` file.
+fn read_stat(path: &str) -> io::Result<(u32, u64)> {
+    let stat = fs::read_to_string(path)?;
+    let pid = stat
+        .split_ascii_whitespace()
+        .next()
+        .and_then(|pid| pid.parse().ok());
+    // The command name is in parentheses, and may contain spaces or parentheses itself.
+    let fields = stat.rsplit_once(')').map(|(_, fields)| fields);
+    let (Some(pid), Some(fields)) = (pid, fields) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{path} unexpected format"),
+        ));
+    };
+    // state ppid pgrp session tty_nr tpgid flags minflt cminflt majflt cmajflt utime stime
+    let mut tokens = fields.split_ascii_whitespace();
+    let _ = tokens.nth(10);
+    let utime = next(&mut tokens)?;
+    let stime = next(&mut tokens)?;
+    Ok((pid, utime.saturating_add(stime)))
+}
+
+/// Reads the clock ticks elapsed, summed across all CPUs, from `/proc/stat`.
+fn read_total_ticks() -> io::Result<u64> {
+    let mut reader = BufReader::new(File::open("/proc/stat")?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    // cpu user nice system idle iowait irq softirq steal guest guest_nice
+    let mut tokens = line.split_ascii_whitespace();
+    if tokens.next() != Some("cpu") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "/proc/stat unexpected string",
+        ));
+    }
+    // Guest time is already included in user time.
+    let mut ret = 0u64;
+    for _ in 0..8 {
+        match next(&mut tokens) {
+            Ok(ticks) => ret = ret.saturating_add(ticks),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(ret)
+}
+
+/// Reads the soft limit on open files from a `limits` file, e.g.
+/// `Max open files            1024                 524288               files`.
+fn read_max_fds(path: &str) -> io::Result<Option<usize>> {
+    let limits = fs::read_to_string(path)?;
+    let limit = limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))
+        .and_then(|limits| limits.split_ascii_whitespace().next());
+    Ok(limit.and_then(|limit| limit.parse().ok()))
+}
+
+/// Calls `f` with each name and value in a file like `/proc/self/status`, e.g. `VmRSS` and
+/// `    1784 kB`.
+fn for_each_field(path: &str, mut f: impl FnMut(&str, &str)) -> io::Result<()> {
+    let contents = fs::read_to_string(path)?;
+    for line in contents.lines() {
+        if let Some((name, value)) = line.split_once(':') {
+            f(name, value);
+        }
+    }
+    Ok(())
+}
+
+/// Parses e.g. `    1784 kB` as bytes.
+fn parse_kb(value: &str) -> Option<u64> {
+    let mut tokens = value.split_ascii_whitespace();
+    let kb = tokens.next()?.parse::<u64>().ok()?;
+    (tokens.next() == Some("kB")).then(|| kb.saturating_mul(1024))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+    use std::fs::{self, File};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn process() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.process(), None);
+        assert_eq!(status.process_cpu_usage(), None);
+
+        let _files = (0..10)
+            .map(|_| File::open("/proc/self/stat").unwrap())
+            .collect::<Vec<_>>();
+        status.update().unwrap();
+
+        let process = status.process().unwrap();
+        println!("process: {:?}", process);
+        assert_eq!(process.pid, std::process::id());
+        assert!(process.rss > 0);
+        assert!(process.threads >= 1);
+        assert!(process.open_fds.unwrap() >= 10);
+        if let Some(fd_usage) = status.process_fd_usage() {
+            println!("process_fd_usage: {}", fd_usage);
+            assert!((0.0..=1.0).contains(&fd_usage));
+        }
+
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(100) {
+            std::hint::black_box(fs::metadata("/proc/self").unwrap());
+        }
+        status.update().unwrap();
+
+        let cpu_usage = status.process_cpu_usage().unwrap();
+        println!("process_cpu_usage: {}", cpu_usage);
+        assert!((0.0..=1.0).contains(&cpu_usage));
+        println!(
+            "process_read_bandwidth: {:?}",
+            status.process_read_bandwidth()
+        );
+        println!(
+            "process_write_bandwidth: {:?}",
+            status.process_write_bandwidth()
+        );
+    }
+
+    #[test]
+    fn process_threads() {
+        let mut status = SimpleServerStatus::default();
+        status.update().unwrap();
+        status.update().unwrap();
+        assert_eq!(status.process_thread_cpu_usage(), None);
+
+        let mut status = SimpleServerStatus::default().with_process_threads(true);
+
+        assert_eq!(status.process_thread_cpu_usage(), None);
+
+        status.update().unwrap();
+
+        let thread = std::thread::Builder::new()
+            .name("busy".to_owned())
+            .spawn(|| {
+                let start = Instant::now();
+                while start.elapsed() < Duration::from_millis(200) {
+                    std::hint::black_box(fs::metadata("/proc/self").unwrap());
+                }
+            })
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        status.update().unwrap();
+        thread.join().unwrap();
+
+        let usage = status.process_thread_cpu_usage().unwrap();
+        println!("process_thread_cpu_usage: {:?}", usage);
+        assert!(usage.iter().any(|(name, _)| name == "busy"));
+        assert!(usage.iter().all(|(_, usage)| (0.0..=1.0).contains(usage)));
+        assert!(usage.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    }
+
+    #[test]
+    fn process_pid() {
+        let mut status = SimpleServerStatus::default().with_process_pid(1);
+
+        status.update().unwrap();
+
+        let process = status.process().unwrap();
+        println!("process: {:?}", process);
+        assert_eq!(process.pid, 1);
+        assert!(process.threads >= 1);
+
+        let mut status = SimpleServerStatus::default().with_process_pid(u32::MAX);
+        assert!(status.update().is_err());
+        assert_eq!(status.process(), None);
+    }
+}
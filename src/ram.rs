@@ -1,7 +1,8 @@
-use crate::{next, sanitize_division};
+use crate::forecast::{Forecast, History};
+use crate::{next, sanitize_division, unix_millis};
 use std::fs::File;
-use std::io;
 use std::io::{BufRead, BufReader, ErrorKind};
+use std::{io, mem};
 
 #[derive(Debug, Default)]
 pub struct RamStatus {
@@ -13,12 +14,28 @@ pub struct RamStatus {
     slab_reclaimable: u64,
     swap_total: u64,
     swap_free: u64,
+    /// Bytes that the kernel can reclaim under memory pressure, but doesn't report as such (e.g.
+    /// ZFS ARC).
+    reclaimable: u64,
+    pub history: History,
 }
 
 impl RamStatus {
-    pub fn update(&mut self) -> io::Result<()> {
-        *self = Self::default();
-        *self = Self::sample()?;
+    pub fn update(&mut self, reclaimable: u64) -> io::Result<()> {
+        // Clear the old measurement, but not the history, in case sampling fails.
+        *self = Self {
+            history: mem::take(&mut self.history),
+            ..Self::default()
+        };
+        let sample = Self::sample()?;
+        *self = Self {
+            reclaimable,
+            history: mem::take(&mut self.history),
+            ..sample
+        };
+        if let Some(usage) = self.usage() {
+            self.history.push(unix_millis(), usage);
+        }
         Ok(())
     }
 
@@ -79,12 +96,17 @@ impl RamStatus {
         sanitize_division(self.swap_used(), self.swap_total)
     }
 
+    pub fn forecast(&self) -> Option<Forecast> {
+        self.history.forecast()
+    }
+
     fn used(&self) -> u64 {
         self.total
             .saturating_sub(self.free)
             .saturating_sub(self.buffers)
             .saturating_sub(self.cached)
             .saturating_sub(self.slab_reclaimable)
+            .saturating_sub(self.reclaimable)
     }
 
     fn swap_used(&self) -> u64 {
@@ -95,6 +117,7 @@ impl RamStatus {
 #[cfg(test)]
 mod tests {
     use crate::SimpleServerStatus;
+    use std::time::Duration;
 
     #[test]
     fn ram() {
@@ -117,4 +140,22 @@ mod tests {
             assert!(swap_usage <= 1.0);
         }
     }
+
+    #[test]
+    fn ram_forecast() {
+        let mut status =
+            SimpleServerStatus::default().with_forecast_window(Duration::from_secs(60));
+
+        assert_eq!(status.ram_forecast(), None);
+
+        for _ in 0..3 {
+            status.update().unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let forecast = status.ram_forecast().unwrap();
+        println!("ram_forecast: {:?}", forecast);
+        assert_eq!(forecast.samples, 3);
+        assert!((0.0..=1.0).contains(&forecast.confidence));
+    }
 }
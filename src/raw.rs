@@ -0,0 +1,46 @@
+use crate::for_each_line;
+use std::io;
+
+#[derive(Debug, Default)]
+pub struct RawStatus {
+    count: Option<usize>,
+}
+
+impl RawStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.count = None;
+        self.count = Some(Self::sample()?);
+        Ok(())
+    }
+
+    fn sample() -> io::Result<usize> {
+        let mut ret = 0usize;
+        for_each_line(&["/proc/net/raw", "/proc/net/raw6"], |line| {
+            if line.contains(':') {
+                ret = ret.saturating_add(1);
+            }
+        })?;
+        Ok(ret)
+    }
+
+    pub fn sockets(&self) -> Option<usize> {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+
+    #[test]
+    fn raw() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.raw_sockets(), None);
+
+        status.update().unwrap();
+
+        let sockets = status.raw_sockets().unwrap();
+        println!("raw_sockets: {}", sockets);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{delta, for_each_line, for_each_paired_field, unix_millis};
+use std::{io, mem};
+
+#[derive(Debug, Default)]
+pub struct SnmpStatus {
+    old: SnmpCounters,
+    new: SnmpCounters,
+}
+
+#[derive(Debug, Default)]
+struct SnmpCounters {
+    unix_millis: u64,
+    tcp_active_opens: u64,
+    tcp_passive_opens: u64,
+    tcp_attempt_fails: u64,
+    tcp_estab_resets: u64,
+    tcp_out_segs: u64,
+    tcp_retrans_segs: u64,
+    tcp_in_errs: u64,
+    udp_in_errors: u64,
+    udp_rcvbuf_errors: u64,
+    udp_sndbuf_errors: u64,
+    udp_no_ports: u64,
+}
+
+impl SnmpStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.old = mem::take(&mut self.new);
+        self.new = SnmpCounters::sample()?;
+        Ok(())
+    }
+
+    pub fn tcp_active_open_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.tcp_active_opens)
+    }
+
+    pub fn tcp_passive_open_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.tcp_passive_opens)
+    }
+
+    pub fn tcp_attempt_fail_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.tcp_attempt_fails)
+    }
+
+    pub fn tcp_established_reset_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.tcp_estab_resets)
+    }
+
+    pub fn tcp_retransmit_ratio(&self) -> Option<f32> {
+        delta!(self.old, self.new, retransmitted, sent)
+    }
+
+    pub fn tcp_in_error_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.tcp_in_errs)
+    }
+
+    pub fn udp_in_error_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.udp_in_errors)
+    }
+
+    pub fn udp_receive_buffer_error_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.udp_rcvbuf_errors)
+    }
+
+    pub fn udp_send_buffer_error_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.udp_sndbuf_errors)
+    }
+
+    pub fn udp_no_port_rate(&self) -> Option<u64> {
+        self.rate(|counters| counters.udp_no_ports)
+    }
+
+    /// Calculates rate of change per second.
+    fn rate(&self, counter: impl Fn(&SnmpCounters) -> u64) -> Option<u64> {
+        let count = counter(&self.new).saturating_sub(counter(&self.old));
+        let millis = self.new.unix_millis.saturating_sub(self.old.unix_millis);
+        count.saturating_mul(1000).checked_div(millis)
+    }
+}
+
+impl SnmpCounters {
+    fn sample() -> io::Result<Self> {
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
+        // TCP counters include IPv6, but UDP counters don't.
+        for_each_paired_field("/proc/net/snmp", |prefix, name, value| {
+            let field = match (prefix, name) {
+                ("Tcp", "ActiveOpens") => &mut ret.tcp_active_opens,
+                ("Tcp", "PassiveOpens") => &mut ret.tcp_passive_opens,
+                ("Tcp", "AttemptFails") => &mut ret.tcp_attempt_fails,
+                ("Tcp", "EstabResets") => &mut ret.tcp_estab_resets,
+                ("Tcp", "OutSegs") => &mut ret.tcp_out_segs,
+                ("Tcp", "RetransSegs") => &mut ret.tcp_retrans_segs,
+                ("Tcp", "InErrs") => &mut ret.tcp_in_errs,
+                ("Udp", name) => match ret.udp_field(name) {
+                    Some(field) => field,
+                    None => return,
+                },
+                _ => return,
+            };
+            *field = value;
+        })?;
+        // e.g. "Udp6InErrors 0"
+        for_each_line(&["/proc/net/snmp6"], |line| {
+            let mut tokens = line.split_ascii_whitespace();
+            let Some(name) = tokens.next().and_then(|name| name.strip_prefix("Udp6")) else {
+                return;
+            };
+            let Some(value) = tokens.next().and_then(|value| value.parse::<u64>().ok()) else {
+                return;
+            };
+            if let Some(field) = ret.udp_field(name) {
+                *field = field.saturating_add(value);
+            }
+        })?;
+        Ok(ret)
+    }
+
+    fn udp_field(&mut self, name: &str) -> Option<&mut u64> {
+        Some(match name {
+            "InErrors" => &mut self.udp_in_errors,
+            "RcvbufErrors" => &mut self.udp_rcvbuf_errors,
+            "SndbufErrors" => &mut self.udp_sndbuf_errors,
+            "NoPorts" => &mut self.udp_no_ports,
+            _ => return None,
+        })
+    }
+
+    fn retransmitted(&self) -> u64 {
+        self.tcp_retrans_segs
+    }
+
+    fn sent(&self) -> u64 {
+        self.tcp_out_segs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snmp::SnmpCounters;
+    use crate::SimpleServerStatus;
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    #[test]
+    fn snmp() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.tcp_active_open_rate(), None);
+        assert_eq!(status.tcp_retransmit_ratio(), None);
+        assert_eq!(status.udp_in_error_rate(), None);
+
+        status.update().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        for _ in 0..10 {
+            let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            let _server = listener.accept().unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        status.update().unwrap();
+
+        let active_open_rate = status.tcp_active_open_rate().unwrap();
+        println!("tcp_active_open_rate: {}", active_open_rate);
+        assert!(active_open_rate > 0);
+        let passive_open_rate = status.tcp_passive_open_rate().unwrap();
+        println!("tcp_passive_open_rate: {}", passive_open_rate);
+        assert!(passive_open_rate > 0);
+        println!(
+            "tcp_attempt_fail_rate: {}",
+            status.tcp_attempt_fail_rate().unwrap()
+        );
+        println!(
+            "tcp_established_reset_rate: {}",
+            status.tcp_established_reset_rate().unwrap()
+        );
+        let retransmit_ratio = status.tcp_retransmit_ratio().unwrap();
+        println!("tcp_retransmit_ratio: {}", retransmit_ratio);
+        assert!((0.0..=1.0).contains(&retransmit_ratio));
+        println!("tcp_in_error_rate: {}", status.tcp_in_error_rate().unwrap());
+        println!("udp_in_error_rate: {}", status.udp_in_error_rate().unwrap());
+        println!(
+            "udp_receive_buffer_error_rate: {}",
+            status.udp_receive_buffer_error_rate().unwrap()
+        );
+        println!(
+            "udp_send_buffer_error_rate: {}",
+            status.udp_send_buffer_error_rate().unwrap()
+        );
+        println!("udp_no_port_rate: {}", status.udp_no_port_rate().unwrap());
+    }
+
+    #[test]
+    fn snmp_counters() {
+        let counters = SnmpCounters::sample().unwrap();
+        println!("snmp_counters: {:?}", counters);
+        assert!(counters.tcp_out_segs > 0);
+    }
+}
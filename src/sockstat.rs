@@ -0,0 +1,205 @@
+use crate::{for_each_line, sanitize_division};
+use std::fs;
+use std::io;
+use std::str::SplitAsciiWhitespace;
+
+#[derive(Debug, Default)]
+pub struct SockstatStatus {
+    sockstat: Option<Sockstat>,
+}
+
+impl SockstatStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.sockstat = None;
+        self.sockstat = Some(Sockstat::sample()?);
+        Ok(())
+    }
+
+    pub fn sockstat(&self) -> Option<Sockstat> {
+        self.sockstat
+    }
+
+    /// Approximates the number of lines in `/proc/net/tcp` and `/proc/net/tcp6`.
+    #[cfg(feature = "tcp")]
+    pub fn tcp_connections(&self) -> Option<usize> {
+        let sockstat = self.sockstat.as_ref()?;
+        Some(sockstat.tcp_in_use.saturating_add(sockstat.tcp_time_wait))
+    }
+
+    #[cfg(feature = "udp")]
+    pub fn udp_sockets(&self) -> Option<usize> {
+        self.sockstat.as_ref().map(|sockstat| sockstat.udp_in_use)
+    }
+
+    pub fn tcp_memory_usage(&self) -> Option<f32> {
+        let sockstat = self.sockstat.as_ref()?;
+        sanitize_division(sockstat.tcp_memory, sockstat.tcp_memory_max)
+    }
+
+    pub fn udp_memory_usage(&self) -> Option<f32> {
+        let sockstat = self.sockstat.as_ref()?;
+        sanitize_division(sockstat.udp_memory, sockstat.udp_memory_max)
+    }
+}
+
+/// Socket totals from `/proc/net/sockstat` and `/proc/net/sockstat6`, which the kernel keeps
+/// track of, so are cheap to read no matter how many sockets there are.
+///
+/// Counts include both IPv4 and IPv6 sockets.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Sockstat {
+    /// Sockets of any kind, including Unix domain sockets.
+    pub sockets: usize,
+    /// TCP sockets, not including those in the `TimeWait` state.
+    pub tcp_in_use: usize,
+    /// TCP sockets no longer attached to a file descriptor.
+    pub tcp_orphan: usize,
+    /// TCP sockets in the `TimeWait` state.
+    pub tcp_time_wait: usize,
+    /// TCP sockets allocated, including those not yet (or no longer) in use.
+    pub tcp_alloc: usize,
+    /// Bytes of memory used by TCP socket buffers.
+    pub tcp_memory: u64,
+    /// Bytes of TCP socket buffer memory above which the kernel enters "memory pressure" mode.
+    pub tcp_memory_pressure: u64,
+    /// Maximum bytes of memory TCP socket buffers may use, from `/proc/sys/net/ipv4/tcp_mem`.
+    pub tcp_memory_max: u64,
+    pub udp_in_use: usize,
+    /// Bytes of memory used by UDP socket buffers.
+    pub udp_memory: u64,
+    /// Bytes of UDP socket buffer memory above which the kernel enters "memory pressure" mode.
+    pub udp_memory_pressure: u64,
+    /// Maximum bytes of memory UDP socket buffers may use, from `/proc/sys/net/ipv4/udp_mem`.
+    pub udp_memory_max: u64,
+    pub udplite_in_use: usize,
+    pub raw_in_use: usize,
+}
+
+impl Sockstat {
+    fn sample() -> io::Result<Self> {
+        // SAFETY: Plain syscall, with no preconditions.
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+        let pages = |pages: u64| pages.saturating_mul(page_size);
+
+        let mut ret = Self::default();
+        let mut error = None;
+        for_each_line(&["/proc/net/sockstat", "/proc/net/sockstat6"], |line| {
+            let mut tokens = line.split_ascii_whitespace();
+            let Some(protocol) = tokens.next() else {
+                return;
+            };
+            // e.g. "TCP: inuse 5 orphan 0 tw 0 alloc 7 mem 1"
+            let fields = match parse_fields(tokens) {
+                Ok(fields) => fields,
+                Err(e) => {
+                    error = Some(e);
+                    return;
+                }
+            };
+            for (name, value) in fields {
+                let field = match (protocol, name) {
+                    ("sockets:", "used") => &mut ret.sockets,
+                    ("TCP:" | "TCP6:", "inuse") => &mut ret.tcp_in_use,
+                    ("TCP:", "orphan") => &mut ret.tcp_orphan,
+                    ("TCP:", "tw") => &mut ret.tcp_time_wait,
+                    ("TCP:", "alloc") => &mut ret.tcp_alloc,
+                    ("TCP:", "mem") => {
+                        ret.tcp_memory = pages(value);
+                        continue;
+                    }
+                    ("UDP:" | "UDP6:", "inuse") => &mut ret.udp_in_use,
+                    ("UDP:", "mem") => {
+                        ret.udp_memory = pages(value);
+                        continue;
+                    }
+                    ("UDPLITE:" | "UDPLITE6:", "inuse") => &mut ret.udplite_in_use,
+                    ("RAW:" | "RAW6:", "inuse") => &mut ret.raw_in_use,
+                    _ => continue,
+                };
+                *field = field.saturating_add(value as usize);
+            }
+        })?;
+        if let Some(e) = error {
+            return Err(e);
+        }
+
+        // min pressure max, in pages.
+        let limits = |path: &str| -> io::Result<(u64, u64)> {
+            let limits = fs::read_to_string(path)?;
+            let mut tokens = limits.split_ascii_whitespace();
+            let _min = crate::next(&mut tokens)?;
+            Ok((
+                pages(crate::next(&mut tokens)?),
+                pages(crate::next(&mut tokens)?),
+            ))
+        };
+        (ret.tcp_memory_pressure, ret.tcp_memory_max) = limits("/proc/sys/net/ipv4/tcp_mem")?;
+        (ret.udp_memory_pressure, ret.udp_memory_max) = limits("/proc/sys/net/ipv4/udp_mem")?;
+        Ok(ret)
+    }
+}
+
+/// Parses `name value` pairs.
+fn parse_fields(mut tokens: SplitAsciiWhitespace<'_>) -> io::Result<Vec<(&str, u64)>> {
+    let mut ret = Vec::new();
+    while let Some(name) = tokens.next() {
+        ret.push((name, crate::next(&mut tokens)?));
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+    use std::net::{TcpListener, UdpSocket};
+
+    #[test]
+    fn sockstat() {
+        let _listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.sockstat(), None);
+        assert_eq!(status.tcp_memory_usage(), None);
+        assert_eq!(status.udp_memory_usage(), None);
+
+        status.update().unwrap();
+
+        let sockstat = status.sockstat().unwrap();
+        println!("sockstat: {:?}", sockstat);
+        assert!(sockstat.sockets >= 2);
+        assert!(sockstat.tcp_in_use >= 1);
+        assert!(sockstat.udp_in_use >= 1);
+        assert!(sockstat.tcp_memory_max > 0);
+        assert!(sockstat.udp_memory_max > 0);
+
+        let tcp_memory_usage = status.tcp_memory_usage().unwrap();
+        println!("tcp_memory_usage: {}", tcp_memory_usage);
+        assert!((0.0..=1.0).contains(&tcp_memory_usage));
+        let udp_memory_usage = status.udp_memory_usage().unwrap();
+        println!("udp_memory_usage: {}", udp_memory_usage);
+        assert!((0.0..=1.0).contains(&udp_memory_usage));
+    }
+
+    #[cfg(all(feature = "tcp", feature = "udp"))]
+    #[test]
+    fn sockstat_mode() {
+        let _listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut status = SimpleServerStatus::default().with_sockstat_mode(true);
+
+        assert_eq!(status.tcp_connections(), None);
+        assert_eq!(status.udp_sockets(), None);
+
+        status.update().unwrap();
+
+        let connections = status.tcp_connections().unwrap();
+        println!("tcp_connections: {}", connections);
+        assert!(connections >= 1);
+        let sockets = status.udp_sockets().unwrap();
+        println!("udp_sockets: {}", sockets);
+        assert!(sockets >= 1);
+    }
+}
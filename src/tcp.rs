@@ -1,40 +1,448 @@
-use std::fs::File;
+use crate::{for_each_line, parse_address};
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 
 #[derive(Debug, Default)]
 pub struct TcpStatus {
-    count: Option<usize>,
+    /// Whether to count sockets per local port.
+    pub local_ports: bool,
+    /// Whether to count sockets per remote address.
+    pub peers: bool,
+    sockets: Option<TcpSockets>,
+}
+
+#[derive(Debug, Default)]
+struct TcpSockets {
+    count: usize,
+    states: TcpStates,
+    /// Only if enabled, since there may be a port per outgoing connection.
+    local_ports: Option<HashMap<u16, TcpStates>>,
+    /// Only if enabled.
+    remote_addresses: Option<HashMap<IpAddr, usize>>,
+    queues: TcpQueues,
+    accept_queues: Vec<TcpAcceptQueue>,
+    /// Only available via netlink.
+    #[cfg_attr(not(feature = "netlink"), allow(unused))]
+    health: Option<TcpHealth>,
+}
+
+/// A single line of `/proc/net/tcp`.
+struct TcpSocket {
+    state: TcpState,
+    local: SocketAddr,
+    remote: SocketAddr,
+    send_queue: u32,
+    receive_queue: u32,
 }
 
 impl TcpStatus {
     pub fn update(&mut self) -> io::Result<()> {
-        self.count = None;
-        self.count = Some(Self::sample()?);
+        self.sockets = None;
+        self.sockets = Some(TcpSockets::sample(self.local_ports, self.peers)?);
         Ok(())
     }
 
-    fn sample() -> io::Result<usize> {
-        let proc_stat = File::open("/proc/net/tcp")?;
-        let reader = BufReader::new(proc_stat);
-        let mut ret = 0usize;
-        for line in reader.lines() {
-            let line = line?;
-            if line.contains(':') {
-                ret = ret.saturating_add(1);
+    pub fn connections(&self) -> Option<usize> {
+        self.sockets.as_ref().map(|sockets| sockets.count)
+    }
+
+    pub fn states(&self) -> Option<TcpStates> {
+        self.sockets.as_ref().map(|sockets| sockets.states)
+    }
+
+    pub fn local_port_states(&self, ports: &[u16]) -> Option<TcpStates> {
+        let local_ports = self.local_ports()?;
+        let mut ret = TcpStates::default();
+        // Count each port once, even if given twice.
+        for port in ports.iter().collect::<HashSet<_>>() {
+            if let Some(states) = local_ports.get(port) {
+                ret.add_all(states);
             }
         }
-        Ok(ret)
+        Some(ret)
     }
 
-    pub fn connections(&self) -> Option<usize> {
-        self.count
+    pub fn local_ports(&self) -> Option<&HashMap<u16, TcpStates>> {
+        self.sockets.as_ref()?.local_ports.as_ref()
+    }
+
+    pub fn top_peers(&self, n: usize, ipv4_prefix: u8, ipv6_prefix: u8) -> Option<Vec<TcpPeer>> {
+        let remote_addresses = self.sockets.as_ref()?.remote_addresses.as_ref()?;
+        let mut networks = HashMap::<(IpAddr, u8), usize>::new();
+        for (&address, &count) in remote_addresses {
+            let network = mask_address(address, ipv4_prefix, ipv6_prefix);
+            let total = networks.entry(network).or_default();
+            *total = total.saturating_add(count);
+        }
+        let mut peers = networks
+            .into_iter()
+            .map(|((address, prefix), count)| TcpPeer {
+                address,
+                prefix,
+                count,
+            })
+            .collect::<Vec<_>>();
+        peers.sort_unstable_by(|a, b| b.count.cmp(&a.count).then(a.address.cmp(&b.address)));
+        peers.truncate(n);
+        Some(peers)
+    }
+
+    pub fn queues(&self) -> Option<TcpQueues> {
+        self.sockets.as_ref().map(|sockets| sockets.queues)
+    }
+
+    pub fn accept_queues(&self) -> Option<&[TcpAcceptQueue]> {
+        self.sockets
+            .as_ref()
+            .map(|sockets| sockets.accept_queues.as_slice())
+    }
+
+    #[cfg(feature = "netlink")]
+    pub fn health(&self) -> Option<TcpHealth> {
+        self.sockets.as_ref()?.health
+    }
+}
+
+impl TcpSockets {
+    fn new(local_ports: bool, peers: bool) -> Self {
+        Self {
+            local_ports: local_ports.then(HashMap::new),
+            remote_addresses: peers.then(HashMap::new),
+            ..Self::default()
+        }
+    }
+
+    fn sample(local_ports: bool, peers: bool) -> io::Result<Self> {
+        #[cfg(all(feature = "netlink", target_os = "linux"))]
+        if let Ok(ret) = Self::new(local_ports, peers).sample_netlink() {
+            return Ok(ret);
+        }
+        Self::new(local_ports, peers).sample_proc()
+    }
+
+    /// Asks the kernel via `NETLINK_SOCK_DIAG`, which avoids formatting and parsing text.
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    fn sample_netlink(mut self) -> io::Result<Self> {
+        use crate::netlink::{
+            read_u32, read_u64, sock_diag, states_mask, InetDiagMsg, ALL_STATES, INET_DIAG_INFO,
+        };
+
+        let mut health = TcpHealth::default();
+        let mut rtts = Vec::new();
+        let mut cwnds = Vec::new();
+        let mut add = |msg: &InetDiagMsg| {
+            self.count = self.count.saturating_add(1);
+            let Some(state) = TcpState::from_number(msg.state) else {
+                return;
+            };
+            self.add(TcpSocket {
+                state,
+                local: msg.local,
+                remote: msg.remote,
+                send_queue: msg.wqueue,
+                receive_queue: msg.rqueue,
+            });
+            // struct tcp_info, see include/uapi/linux/tcp.h. Older kernels report fewer fields,
+            // so treat missing ones as 0.
+            let Some(info) = msg.attribute(INET_DIAG_INFO) else {
+                return;
+            };
+            let Some(rtt) = read_u32(info, 68) else {
+                return;
+            };
+            let lost = read_u32(info, 32).unwrap_or(0) as u64;
+            let cwnd = read_u32(info, 80).unwrap_or(0);
+            let total_retrans = read_u32(info, 100).unwrap_or(0) as u64;
+            let delivery_rate = read_u64(info, 160).unwrap_or(0);
+            rtts.push(rtt);
+            cwnds.push(cwnd);
+            health.connections = health.connections.saturating_add(1);
+            health.lost = health.lost.saturating_add(lost);
+            health.retransmits = health.retransmits.saturating_add(total_retrans);
+            health.delivery_rate = health.delivery_rate.saturating_add(delivery_rate);
+        };
+        // Only established connections need `tcp_info`, so don't have the kernel fill it in for
+        // the rest (e.g. many `TimeWait` sockets).
+        let established = states_mask([TcpState::Established as u8]);
+        let others = ALL_STATES & !established;
+        sock_diag(libc::IPPROTO_TCP as u8, others, 0, &mut add)?;
+        sock_diag(
+            libc::IPPROTO_TCP as u8,
+            established,
+            1 << (INET_DIAG_INFO - 1),
+            &mut add,
+        )?;
+        rtts.sort_unstable();
+        cwnds.sort_unstable();
+        let rtt_percentile = |p| Duration::from_micros(percentile(&rtts, p) as u64);
+        health.rtt_p50 = rtt_percentile(50);
+        health.rtt_p90 = rtt_percentile(90);
+        health.rtt_p99 = rtt_percentile(99);
+        health.cwnd_p50 = percentile(&cwnds, 50);
+        self.health = Some(health);
+        Ok(self)
+    }
+
+    fn sample_proc(mut self) -> io::Result<Self> {
+        for_each_line(&["/proc/net/tcp", "/proc/net/tcp6"], |line| {
+            if !line.contains(':') {
+                return;
+            }
+            self.count = self.count.saturating_add(1);
+
+            if let Some(socket) = TcpSocket::parse(line) {
+                self.add(socket);
+            }
+        })?;
+        Ok(self)
+    }
+
+    fn add(&mut self, socket: TcpSocket) {
+        self.states.add(socket.state, 1);
+        if let Some(local_ports) = &mut self.local_ports {
+            local_ports
+                .entry(socket.local.port())
+                .or_default()
+                .add(socket.state, 1);
+        }
+        if let Some(remote_addresses) = &mut self.remote_addresses {
+            // Listening sockets have no peer.
+            if !socket.remote.ip().is_unspecified() {
+                let count = remote_addresses.entry(socket.remote.ip()).or_default();
+                *count = count.saturating_add(1);
+            }
+        }
+        match socket.state {
+            TcpState::Established => self.queues.add(&socket),
+            // The receive queue of a listening socket holds connections waiting to be accepted.
+            TcpState::Listen => self.accept_queues.push(TcpAcceptQueue {
+                local: socket.local,
+                pending: socket.receive_queue,
+            }),
+            _ => {}
+        }
+    }
+}
+
+impl TcpSocket {
+    fn parse(line: &str) -> Option<Self> {
+        // sl local_address rem_address st tx_queue:rx_queue ...
+        let mut tokens = line.split_ascii_whitespace().skip(1);
+        let local = parse_address(tokens.next()?)?;
+        let remote = parse_address(tokens.next()?)?;
+        let state = TcpState::from_hex(tokens.next()?)?;
+        let (send_queue, receive_queue) = tokens.next()?.split_once(':')?;
+        Some(Self {
+            state,
+            local,
+            remote,
+            send_queue: u32::from_str_radix(send_queue, 16).ok()?,
+            receive_queue: u32::from_str_radix(receive_queue, 16).ok()?,
+        })
+    }
+}
+
+/// Returns the `p`th percentile (0..=100) of `sorted`, by the nearest-rank method, or 0 if empty.
+#[cfg_attr(not(all(feature = "netlink", target_os = "linux")), allow(unused))]
+fn percentile(sorted: &[u32], p: usize) -> u32 {
+    let rank = sorted.len().saturating_mul(p).div_ceil(100);
+    sorted
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Returns the network of length `ipv4_prefix` or `ipv6_prefix` containing `address`, along with
+/// the actual prefix length.
+fn mask_address(address: IpAddr, ipv4_prefix: u8, ipv6_prefix: u8) -> (IpAddr, u8) {
+    match address {
+        IpAddr::V4(address) => {
+            let prefix = ipv4_prefix.min(32);
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            (IpAddr::V4((u32::from(address) & mask).into()), prefix)
+        }
+        IpAddr::V6(address) => {
+            let prefix = ipv6_prefix.min(128);
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            (IpAddr::V6((u128::from(address) & mask).into()), prefix)
+        }
+    }
+}
+
+/// Bytes queued in established TCP sockets.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TcpQueues {
+    /// Bytes sent by the application but not yet acknowledged by the peer.
+    pub send_bytes: u64,
+    /// Bytes received from the peer but not yet read by the application.
+    pub receive_bytes: u64,
+    /// Number of sockets with a non-empty send queue.
+    pub send_sockets: usize,
+    /// Number of sockets with a non-empty receive queue.
+    pub receive_sockets: usize,
+}
+
+impl TcpQueues {
+    fn add(&mut self, socket: &TcpSocket) {
+        self.send_bytes = self.send_bytes.saturating_add(socket.send_queue as u64);
+        self.receive_bytes = self
+            .receive_bytes
+            .saturating_add(socket.receive_queue as u64);
+        if socket.send_queue != 0 {
+            self.send_sockets = self.send_sockets.saturating_add(1);
+        }
+        if socket.receive_queue != 0 {
+            self.receive_sockets = self.receive_sockets.saturating_add(1);
+        }
+    }
+}
+
+/// A listening TCP socket and its accept queue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TcpAcceptQueue {
+    /// The address the socket is listening on.
+    pub local: SocketAddr,
+    /// Number of connections waiting to be accepted by the application.
+    pub pending: u32,
+}
+
+/// Aggregate health of established TCP connections, from the kernel's `struct tcp_info`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TcpHealth {
+    /// Number of established connections measured.
+    pub connections: usize,
+    /// Median smoothed round trip time.
+    pub rtt_p50: Duration,
+    /// 90th percentile smoothed round trip time.
+    pub rtt_p90: Duration,
+    /// 99th percentile smoothed round trip time.
+    pub rtt_p99: Duration,
+    /// Median congestion window, in segments.
+    pub cwnd_p50: u32,
+    /// Total segments retransmitted, over the lifetime of the connections.
+    pub retransmits: u64,
+    /// Total segments currently presumed lost.
+    pub lost: u64,
+    /// Total of the most recent delivery rate of each connection, in bytes per second.
+    pub delivery_rate: u64,
+}
+
+/// A remote address (or network) with TCP sockets connected to it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TcpPeer {
+    /// The remote address, or the first address of the remote network.
+    pub address: IpAddr,
+    /// Length of the network prefix, e.g. 32 for a single IPv4 address.
+    pub prefix: u8,
+    /// Number of TCP sockets connected to the address (or network).
+    pub count: usize,
+}
+
+/// The state of a TCP socket, as reported by the kernel.
+///
+/// See `include/net/tcp_states.h` in the Linux source.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TcpState {
+    Established = 1,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    NewSynRecv,
+}
+
+impl TcpState {
+    /// All states, in the order the kernel numbers them.
+    pub const ALL: [Self; 12] = [
+        Self::Established,
+        Self::SynSent,
+        Self::SynRecv,
+        Self::FinWait1,
+        Self::FinWait2,
+        Self::TimeWait,
+        Self::Close,
+        Self::CloseWait,
+        Self::LastAck,
+        Self::Listen,
+        Self::Closing,
+        Self::NewSynRecv,
+    ];
+
+    /// Parses the `st` column of `/proc/net/tcp`, e.g. `"0A"` for `Listen`.
+    fn from_hex(token: &str) -> Option<Self> {
+        Self::from_number(u8::from_str_radix(token, 16).ok()?)
+    }
+
+    fn from_number(number: u8) -> Option<Self> {
+        Self::ALL.get((number as usize).checked_sub(1)?).copied()
+    }
+
+    fn index(self) -> usize {
+        self as usize - 1
+    }
+}
+
+/// Number of TCP sockets in each state.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TcpStates {
+    counts: [usize; TcpState::ALL.len()],
+}
+
+impl TcpStates {
+    /// Returns the number of sockets in `state`.
+    pub fn get(&self, state: TcpState) -> usize {
+        self.counts[state.index()]
+    }
+
+    /// Returns the number of sockets in the `Established` state.
+    pub fn established(&self) -> usize {
+        self.get(TcpState::Established)
+    }
+
+    /// Returns the number of sockets in any state.
+    pub fn total(&self) -> usize {
+        self.counts
+            .iter()
+            .fold(0usize, |total, &count| total.saturating_add(count))
+    }
+
+    /// Iterates over every state and its number of sockets.
+    pub fn iter(&self) -> impl Iterator<Item = (TcpState, usize)> + '_ {
+        TcpState::ALL
+            .iter()
+            .map(move |&state| (state, self.get(state)))
+    }
+
+    fn add(&mut self, state: TcpState, count: usize) {
+        let counter = &mut self.counts[state.index()];
+        *counter = counter.saturating_add(count);
+    }
+
+    fn add_all(&mut self, other: &Self) {
+        for (state, count) in other.iter() {
+            self.add(state, count);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::SimpleServerStatus;
+    use crate::parse_address;
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    use crate::tcp::TcpSockets;
+    use crate::tcp::{mask_address, percentile};
+    use crate::{SimpleServerStatus, TcpState};
+    use std::io::Write;
+    use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream};
+    use std::time::Duration;
 
     #[test]
     fn tcp() {
@@ -47,4 +455,243 @@ mod tests {
         let connections = status.tcp_connections().unwrap();
         println!("tcp_connections: {}", connections);
     }
+
+    #[test]
+    fn tcp_states() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let _server = listener.accept().unwrap();
+
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.tcp_states(), None);
+
+        status.update().unwrap();
+
+        let states = status.tcp_states().unwrap();
+        for (state, count) in states.iter() {
+            println!("tcp_states {:?}: {}", state, count);
+        }
+        assert!(states.get(TcpState::Listen) >= 1);
+        assert!(states.established() >= 2);
+        assert!(states.total() <= status.tcp_connections().unwrap());
+    }
+
+    #[test]
+    fn tcp_local_ports() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let _server = listener.accept().unwrap();
+
+        let mut status = SimpleServerStatus::default();
+        status.update().unwrap();
+        assert_eq!(status.tcp_local_port_states(&[port]), None);
+
+        let mut status = SimpleServerStatus::default().with_tcp_local_ports(true);
+
+        assert_eq!(status.tcp_local_port_states(&[port]), None);
+        assert!(status.tcp_local_ports().is_none());
+
+        status.update().unwrap();
+
+        let states = status.tcp_local_port_states(&[port]).unwrap();
+        println!("tcp_local_port_states {}: {:?}", port, states);
+        assert_eq!(states.get(TcpState::Listen), 1);
+        assert_eq!(states.established(), 1);
+        assert_eq!(status.tcp_local_port_states(&[port, port]), Some(states));
+
+        let local_ports = status.tcp_local_ports().unwrap();
+        println!("tcp_local_ports: {}", local_ports.len());
+        assert_eq!(local_ports.get(&port), Some(&states));
+    }
+
+    #[test]
+    fn tcp_top_peers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let _server = listener.accept().unwrap();
+
+        let mut status = SimpleServerStatus::default();
+        status.update().unwrap();
+        assert_eq!(status.tcp_top_peers(10), None);
+
+        let mut status = SimpleServerStatus::default().with_tcp_peers(true);
+
+        assert_eq!(status.tcp_top_peers(10), None);
+
+        status.update().unwrap();
+
+        let peers = status.tcp_top_peers(10).unwrap();
+        println!("tcp_top_peers: {:?}", peers);
+        assert!(peers.len() <= 10);
+        assert!(peers.windows(2).all(|w| w[0].count >= w[1].count));
+        let localhost = peers
+            .iter()
+            .find(|peer| peer.address == IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .unwrap();
+        assert_eq!(localhost.prefix, 32);
+        assert!(localhost.count >= 2);
+
+        let networks = status.tcp_top_peer_networks(10, 24, 64).unwrap();
+        println!("tcp_top_peer_networks: {:?}", networks);
+        let network = networks
+            .iter()
+            .find(|peer| peer.address == IpAddr::V4(Ipv4Addr::new(127, 0, 0, 0)))
+            .unwrap();
+        assert_eq!(network.prefix, 24);
+        assert!(network.count >= localhost.count);
+    }
+
+    #[test]
+    fn tcp_queues() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut client = TcpStream::connect(address).unwrap();
+        let _pending = TcpStream::connect(address).unwrap();
+        let _server = listener.accept().unwrap();
+        client.write_all(&[0; 1000]).unwrap();
+        // Give the kernel time to deliver the bytes.
+        std::thread::sleep(Duration::from_millis(50));
+
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.tcp_queues(), None);
+        assert!(status.tcp_accept_queues().is_none());
+
+        status.update().unwrap();
+
+        let queues = status.tcp_queues().unwrap();
+        println!("tcp_queues: {:?}", queues);
+        assert!(queues.receive_bytes >= 1000);
+        assert!(queues.receive_sockets >= 1);
+
+        let accept_queues = status.tcp_accept_queues().unwrap();
+        println!("tcp_accept_queues: {:?}", accept_queues);
+        let accept_queue = accept_queues
+            .iter()
+            .find(|accept_queue| accept_queue.local == address)
+            .unwrap();
+        assert_eq!(accept_queue.pending, 1);
+    }
+
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    #[test]
+    fn tcp_netlink() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let _server = listener.accept().unwrap();
+
+        let netlink = TcpSockets::new(true, false).sample_netlink().unwrap();
+        let proc = TcpSockets::new(true, false).sample_proc().unwrap();
+        println!("tcp_netlink: {:?}", netlink.states);
+        println!("tcp_proc: {:?}", proc.states);
+        let (netlink, proc) = (netlink.local_ports.unwrap(), proc.local_ports.unwrap());
+        assert_eq!(netlink.get(&port), proc.get(&port));
+        assert_eq!(netlink[&port].get(TcpState::Listen), 1);
+        assert_eq!(netlink[&port].established(), 1);
+    }
+
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    #[test]
+    fn tcp_netlink_bound_inactive() {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        // A socket that is bound, but neither connected nor listening.
+        // SAFETY: Plain syscalls, on a socket owned by `fd`.
+        let (_socket, port) = unsafe {
+            let fd = libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0);
+            assert!(fd >= 0);
+            let fd = OwnedFd::from_raw_fd(fd);
+            let mut address: libc::sockaddr_in = std::mem::zeroed();
+            address.sin_family = libc::AF_INET as libc::sa_family_t;
+            address.sin_addr.s_addr = u32::from(Ipv4Addr::LOCALHOST).to_be();
+            let mut len = std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+            let address = &mut address as *mut libc::sockaddr_in as *mut libc::sockaddr;
+            assert_eq!(libc::bind(fd.as_raw_fd(), address, len), 0);
+            assert_eq!(libc::getsockname(fd.as_raw_fd(), address, &mut len), 0);
+            (
+                fd,
+                u16::from_be((*(address as *mut libc::sockaddr_in)).sin_port),
+            )
+        };
+
+        let netlink = TcpSockets::new(true, false).sample_netlink().unwrap();
+        let proc = TcpSockets::new(true, false).sample_proc().unwrap();
+        assert_eq!(proc.local_ports.unwrap().get(&port), None);
+        assert_eq!(netlink.local_ports.unwrap().get(&port), None);
+    }
+
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    #[test]
+    fn tcp_health() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let _server = listener.accept().unwrap();
+        client.write_all(&[0; 1000]).unwrap();
+
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.tcp_health(), None);
+
+        status.update().unwrap();
+
+        let health = status.tcp_health().unwrap();
+        println!("tcp_health: {:?}", health);
+        assert!(health.connections >= 2);
+        assert!(health.rtt_p50 <= health.rtt_p90);
+        assert!(health.rtt_p90 <= health.rtt_p99);
+        assert!(health.cwnd_p50 > 0);
+    }
+
+    #[test]
+    fn tcp_percentile() {
+        assert_eq!(percentile(&[], 50), 0);
+        assert_eq!(percentile(&[10], 99), 10);
+        assert_eq!(percentile(&[10, 20], 50), 10);
+        assert_eq!(percentile(&[10, 20], 90), 20);
+        assert_eq!(percentile(&[10, 20], 99), 20);
+        let sorted = (1..=100).collect::<Vec<_>>();
+        assert_eq!(percentile(&sorted, 0), 1);
+        assert_eq!(percentile(&sorted, 50), 50);
+        assert_eq!(percentile(&sorted, 99), 99);
+        assert_eq!(percentile(&sorted, 100), 100);
+    }
+
+    #[test]
+    fn tcp_address() {
+        let address = parse_address("0100007F:0016").unwrap();
+        if cfg!(target_endian = "little") {
+            assert_eq!(address, "127.0.0.1:22".parse().unwrap());
+        }
+        assert_eq!(address.port(), 22);
+
+        let address = parse_address("B80D0120000000000000000001000000:01BB").unwrap();
+        if cfg!(target_endian = "little") {
+            assert_eq!(address, "[2001:db8::1]:443".parse().unwrap());
+        }
+        assert_eq!(address.port(), 443);
+
+        let address = parse_address("0000000000000000FFFF00000100007F:0016").unwrap();
+        if cfg!(target_endian = "little") {
+            assert_eq!(address, "127.0.0.1:22".parse().unwrap());
+        }
+
+        assert_eq!(parse_address("0100007F"), None);
+        assert_eq!(parse_address("0100007:0016"), None);
+
+        assert_eq!(
+            mask_address("192.0.2.77".parse().unwrap(), 24, 64),
+            ("192.0.2.0".parse().unwrap(), 24)
+        );
+        assert_eq!(
+            mask_address("2001:db8::1".parse().unwrap(), 24, 64),
+            ("2001:db8::".parse().unwrap(), 64)
+        );
+        assert_eq!(
+            mask_address("192.0.2.77".parse().unwrap(), 0, 0),
+            ("0.0.0.0".parse().unwrap(), 0)
+        );
+    }
 }
@@ -1,40 +1,143 @@
-use std::fs::File;
+use crate::{for_each_line, parse_address, unix_millis};
+use std::collections::HashMap;
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::mem;
 
 #[derive(Debug, Default)]
 pub struct UdpStatus {
-    count: Option<usize>,
+    old: Option<UdpSockets>,
+    new: Option<UdpSockets>,
+}
+
+#[derive(Debug, Default)]
+struct UdpSockets {
+    unix_millis: u64,
+    count: usize,
+    /// Datagrams dropped by each socket (by inode) since it was opened, e.g. because the receive
+    /// buffer was full.
+    socket_drops: HashMap<u64, u64>,
+    receive_queue: u64,
+    local_port_drops: HashMap<u16, u64>,
 }
 
 impl UdpStatus {
     pub fn update(&mut self) -> io::Result<()> {
-        self.count = None;
-        self.count = Some(Self::sample()?);
+        self.old = mem::take(&mut self.new);
+        self.new = Some(UdpSockets::sample()?);
         Ok(())
     }
 
-    fn sample() -> io::Result<usize> {
-        let proc_stat = File::open("/proc/net/udp")?;
-        let reader = BufReader::new(proc_stat);
-        let mut ret = 0usize;
-        for line in reader.lines() {
-            let line = line?;
-            if line.contains(':') {
-                ret = ret.saturating_add(1);
-            }
+    pub fn sockets(&self) -> Option<usize> {
+        self.new.as_ref().map(|new| new.count)
+    }
+
+    /// Calculates drops per second, summed across sockets present in both samples, so that
+    /// closing a socket doesn't hide new drops.
+    pub fn drop_rate(&self) -> Option<u64> {
+        let (old, new) = (self.old.as_ref()?, self.new.as_ref()?);
+        let drops = new
+            .socket_drops
+            .iter()
+            .filter_map(|(inode, new)| Some(new.saturating_sub(*old.socket_drops.get(inode)?)))
+            .fold(0u64, u64::saturating_add);
+        let millis = new.unix_millis.saturating_sub(old.unix_millis);
+        drops.saturating_mul(1000).checked_div(millis)
+    }
+
+    pub fn local_port_drops(&self) -> Option<&HashMap<u16, u64>> {
+        self.new.as_ref().map(|new| &new.local_port_drops)
+    }
+
+    pub fn receive_queue(&self) -> Option<u64> {
+        self.new.as_ref().map(|new| new.receive_queue)
+    }
+}
+
+impl UdpSockets {
+    fn sample() -> io::Result<Self> {
+        #[cfg(all(feature = "netlink", target_os = "linux"))]
+        if let Ok(ret) = Self::sample_netlink() {
+            return Ok(ret);
         }
+        Self::sample_proc()
+    }
+
+    /// Asks the kernel via `NETLINK_SOCK_DIAG`, which avoids formatting and parsing text.
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    fn sample_netlink() -> io::Result<Self> {
+        use crate::netlink::{read_u32, states_mask, INET_DIAG_SKMEMINFO};
+
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
+        // UDP sockets reuse TCP's state numbers: `TCP_ESTABLISHED` (1) if connected, otherwise
+        // `TCP_CLOSE` (7).
+        crate::netlink::sock_diag(
+            libc::IPPROTO_UDP as u8,
+            states_mask([1, 7]),
+            1 << (INET_DIAG_SKMEMINFO - 1),
+            |msg| {
+                // SK_MEMINFO_DROPS, see include/uapi/linux/sock_diag.h.
+                let drops = msg
+                    .attribute(INET_DIAG_SKMEMINFO)
+                    .and_then(|meminfo| read_u32(meminfo, 8 * 4))
+                    .unwrap_or(0);
+                ret.add(msg.inode as u64, msg.local.port(), msg.rqueue, drops);
+            },
+        )?;
         Ok(ret)
     }
 
-    pub fn sockets(&self) -> Option<usize> {
-        self.count
+    fn sample_proc() -> io::Result<Self> {
+        let mut ret = Self {
+            unix_millis: unix_millis(),
+            ..Self::default()
+        };
+        for_each_line(&["/proc/net/udp", "/proc/net/udp6"], |line| {
+            if !line.contains(':') {
+                return;
+            }
+
+            // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid
+            // timeout inode ref pointer drops
+            let mut tokens = line.split_ascii_whitespace();
+            let local_port = tokens
+                .nth(1)
+                .and_then(parse_address)
+                .map(|local| local.port());
+            let receive_queue = tokens
+                .nth(2)
+                .and_then(|queues| queues.split_once(':'))
+                .and_then(|(_, receive_queue)| u32::from_str_radix(receive_queue, 16).ok());
+            let inode = tokens.nth(4).and_then(|inode| inode.parse::<u64>().ok());
+            let drops = tokens.nth(2).and_then(|drops| drops.parse::<u32>().ok());
+            match (local_port, receive_queue, inode, drops) {
+                (Some(local_port), Some(receive_queue), Some(inode), Some(drops)) => {
+                    ret.add(inode, local_port, receive_queue, drops)
+                }
+                _ => ret.count = ret.count.saturating_add(1),
+            }
+        })?;
+        Ok(ret)
+    }
+
+    fn add(&mut self, inode: u64, local_port: u16, receive_queue: u32, drops: u32) {
+        self.count = self.count.saturating_add(1);
+        self.receive_queue = self.receive_queue.saturating_add(receive_queue as u64);
+        self.socket_drops.insert(inode, drops as u64);
+        let port_drops = self.local_port_drops.entry(local_port).or_default();
+        *port_drops = port_drops.saturating_add(drops as u64);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::udp::{UdpSockets, UdpStatus};
     use crate::SimpleServerStatus;
+    use std::collections::HashMap;
+    use std::net::UdpSocket;
+    use std::time::Duration;
 
     #[test]
     fn udp() {
@@ -47,4 +150,73 @@ mod tests {
         let connections = status.udp_sockets().unwrap();
         println!("udp_sockets: {}", connections);
     }
+
+    #[test]
+    fn udp_drops() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = receiver.local_addr().unwrap().port();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(receiver.local_addr().unwrap()).unwrap();
+        sender.send(&[0; 100]).unwrap();
+
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.udp_drop_rate(), None);
+        assert!(status.udp_local_port_drops().is_none());
+        assert_eq!(status.udp_receive_queue(), None);
+
+        status.update().unwrap();
+
+        assert_eq!(status.udp_drop_rate(), None);
+        let receive_queue = status.udp_receive_queue().unwrap();
+        println!("udp_receive_queue: {}", receive_queue);
+        assert!(receive_queue >= 100);
+
+        // Overflow the receive buffer.
+        for _ in 0..10000 {
+            let _ = sender.send(&[0; 1000]);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        status.update().unwrap();
+
+        let drop_rate = status.udp_drop_rate().unwrap();
+        println!("udp_drop_rate: {}", drop_rate);
+        assert!(drop_rate > 0);
+        let drops = status.udp_local_port_drops().unwrap()[&port];
+        println!("udp_local_port_drops {}: {}", port, drops);
+        assert!(drops > 0);
+    }
+
+    #[cfg(all(feature = "netlink", target_os = "linux"))]
+    #[test]
+    fn udp_netlink() {
+        let _sockets = (0..10)
+            .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+            .collect::<Vec<_>>();
+
+        let netlink = UdpSockets::sample_netlink().unwrap();
+        let proc = UdpSockets::sample_proc().unwrap();
+        println!("udp_netlink: {}, udp_proc: {}", netlink.count, proc.count);
+        assert!(netlink.count >= 10);
+        assert!(proc.count >= 10);
+        assert!(netlink
+            .socket_drops
+            .keys()
+            .any(|inode| proc.socket_drops.contains_key(inode)));
+    }
+
+    #[test]
+    fn udp_drop_rate_closed_socket() {
+        let sample = |unix_millis, socket_drops: &[(u64, u64)]| UdpSockets {
+            unix_millis,
+            socket_drops: socket_drops.iter().copied().collect::<HashMap<_, _>>(),
+            ..UdpSockets::default()
+        };
+        // Socket 1 closed after dropping 1000 datagrams, while socket 2 dropped 10 more.
+        let status = UdpStatus {
+            old: Some(sample(1000, &[(1, 1000), (2, 5)])),
+            new: Some(sample(2000, &[(2, 15), (3, 7)])),
+        };
+        assert_eq!(status.drop_rate(), Some(10));
+    }
 }
@@ -0,0 +1,51 @@
+use crate::for_each_line;
+use std::io;
+
+#[derive(Debug, Default)]
+pub struct UdpLiteStatus {
+    count: Option<usize>,
+}
+
+impl UdpLiteStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.count = None;
+        self.count = Some(Self::sample()?);
+        Ok(())
+    }
+
+    fn sample() -> io::Result<usize> {
+        let mut ret = 0usize;
+        let result = for_each_line(&["/proc/net/udplite", "/proc/net/udplite6"], |line| {
+            if line.contains(':') {
+                ret = ret.saturating_add(1);
+            }
+        });
+        match result {
+            Ok(()) => Ok(ret),
+            // The kernel doesn't support UDP-Lite, so there can't be any sockets.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn sockets(&self) -> Option<usize> {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+
+    #[test]
+    fn udplite() {
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.udplite_sockets(), None);
+
+        status.update().unwrap();
+
+        let sockets = status.udplite_sockets().unwrap();
+        println!("udplite_sockets: {}", sockets);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::for_each_line;
+use std::io;
+
+#[derive(Debug, Default)]
+pub struct UnixStatus {
+    sockets: Option<UnixSockets>,
+}
+
+impl UnixStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.sockets = None;
+        self.sockets = Some(UnixSockets::sample()?);
+        Ok(())
+    }
+
+    pub fn sockets(&self) -> Option<UnixSockets> {
+        self.sockets
+    }
+}
+
+/// Number of Unix domain sockets of each type, and in each state.
+///
+/// Every socket has exactly one type, and exactly one state.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct UnixSockets {
+    /// `SOCK_STREAM` sockets.
+    pub stream: usize,
+    /// `SOCK_DGRAM` sockets.
+    pub datagram: usize,
+    /// `SOCK_SEQPACKET` sockets.
+    pub seq_packet: usize,
+    /// Sockets accepting connections.
+    pub listening: usize,
+    /// Sockets that aren't connected (nor listening).
+    pub unconnected: usize,
+    pub connecting: usize,
+    pub connected: usize,
+    pub disconnecting: usize,
+}
+
+impl UnixSockets {
+    fn sample() -> io::Result<Self> {
+        // See `unix_seq_show` in net/unix/af_unix.c.
+        const SOCK_STREAM: u16 = 1;
+        const SOCK_DGRAM: u16 = 2;
+        const SOCK_SEQPACKET: u16 = 5;
+        const SS_UNCONNECTED: u8 = 1;
+        const SS_CONNECTING: u8 = 2;
+        const SS_CONNECTED: u8 = 3;
+        const SS_DISCONNECTING: u8 = 4;
+        const SO_ACCEPTCON: u32 = 1 << 16;
+
+        let mut ret = Self::default();
+        for_each_line(&["/proc/net/unix"], |line| {
+            // Num: RefCount Protocol Flags Type St Inode Path
+            let mut tokens = line.split_ascii_whitespace();
+            if !tokens.next().is_some_and(|num| num.ends_with(':')) {
+                return;
+            }
+            let flags = tokens
+                .nth(2)
+                .and_then(|flags| u32::from_str_radix(flags, 16).ok());
+            let kind = tokens
+                .next()
+                .and_then(|kind| u16::from_str_radix(kind, 16).ok());
+            let state = tokens
+                .next()
+                .and_then(|state| u8::from_str_radix(state, 16).ok());
+
+            let kind = match kind {
+                Some(SOCK_STREAM) => &mut ret.stream,
+                Some(SOCK_DGRAM) => &mut ret.datagram,
+                Some(SOCK_SEQPACKET) => &mut ret.seq_packet,
+                _ => return,
+            };
+            *kind = kind.saturating_add(1);
+
+            let state = match (flags, state) {
+                (Some(flags), _) if flags & SO_ACCEPTCON != 0 => &mut ret.listening,
+                (_, Some(SS_UNCONNECTED)) => &mut ret.unconnected,
+                (_, Some(SS_CONNECTING)) => &mut ret.connecting,
+                (_, Some(SS_CONNECTED)) => &mut ret.connected,
+                (_, Some(SS_DISCONNECTING)) => &mut ret.disconnecting,
+                _ => return,
+            };
+            *state = state.saturating_add(1);
+        })?;
+        Ok(ret)
+    }
+
+    /// Returns the number of sockets of any type.
+    pub fn total(&self) -> usize {
+        self.stream
+            .saturating_add(self.datagram)
+            .saturating_add(self.seq_packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SimpleServerStatus;
+    use std::os::unix::net::{UnixDatagram, UnixStream};
+
+    #[test]
+    fn unix() {
+        let _stream = UnixStream::pair().unwrap();
+        let _datagram = UnixDatagram::pair().unwrap();
+
+        let mut status = SimpleServerStatus::default();
+
+        assert_eq!(status.unix_sockets(), None);
+
+        status.update().unwrap();
+
+        let sockets = status.unix_sockets().unwrap();
+        println!("unix_sockets: {:?}", sockets);
+        assert!(sockets.stream >= 2);
+        assert!(sockets.datagram >= 2);
+        assert!(sockets.connected >= 4);
+        assert!(sockets.total() >= 4);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn unix_non_utf8_name() {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::SocketAddr;
+
+        let addr = SocketAddr::from_abstract_name(b"\xff\xfe\x80bad").unwrap();
+        let _datagram = UnixDatagram::bind_addr(&addr).unwrap();
+
+        let mut status = SimpleServerStatus::default();
+        status.update().unwrap();
+
+        let sockets = status.unix_sockets().unwrap();
+        assert!(sockets.datagram >= 1);
+        assert!(sockets.unconnected >= 1);
+    }
+}
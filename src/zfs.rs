@@ -0,0 +1,150 @@
+use crate::{delta, next};
+use std::{fs, io, mem};
+
+#[derive(Debug, Default)]
+pub struct ZfsStatus {
+    /// Whether the shrinkable part of the ARC counts as reclaimable in `ram_usage`.
+    pub arc_reclaimable: bool,
+    old: Option<ZfsArc>,
+    new: Option<ZfsArc>,
+}
+
+impl ZfsStatus {
+    pub fn update(&mut self) -> io::Result<()> {
+        self.old = mem::take(&mut self.new);
+        self.new = ZfsArc::sample()?;
+        Ok(())
+    }
+
+    pub fn arc(&self) -> Option<ZfsArc> {
+        self.new
+    }
+
+    pub fn arc_hit_ratio(&self) -> Option<f32> {
+        let (old, new) = (self.old.as_ref()?, self.new.as_ref()?);
+        delta!(old, new, hits, accesses)
+    }
+
+    /// Bytes of ARC that the kernel can reclaim, if `arc_reclaimable`.
+    pub fn reclaimable(&self) -> u64 {
+        match self.new {
+            Some(arc) if self.arc_reclaimable => arc.shrinkable(),
+            _ => 0,
+        }
+    }
+}
+
+/// ZFS Adaptive Replacement Cache statistics, from `/proc/spl/kstat/zfs/arcstats`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ZfsArc {
+    /// Bytes of memory used.
+    pub size: u64,
+    /// Bytes of memory below which the ARC won't shrink.
+    pub c_min: u64,
+    /// Bytes of memory above which the ARC won't grow.
+    pub c_max: u64,
+    /// Reads served from the ARC, since boot.
+    pub hits: u64,
+    /// Reads not served from the ARC, since boot.
+    pub misses: u64,
+}
+
+impl ZfsArc {
+    fn sample() -> io::Result<Option<Self>> {
+        match fs::read_to_string("/proc/spl/kstat/zfs/arcstats") {
+            Ok(arcstats) => Self::parse(&arcstats).map(Some),
+            // ZFS isn't loaded.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse(arcstats: &str) -> io::Result<Self> {
+        let mut ret = Self::default();
+        for line in arcstats.lines() {
+            // name type data
+            let mut tokens = line.split_ascii_whitespace();
+            let field = match tokens.next() {
+                Some("size") => &mut ret.size,
+                Some("c_min") => &mut ret.c_min,
+                Some("c_max") => &mut ret.c_max,
+                Some("hits") => &mut ret.hits,
+                Some("misses") => &mut ret.misses,
+                _ => continue,
+            };
+            let _kind = tokens.next();
+            *field = next(&mut tokens)?;
+        }
+        Ok(ret)
+    }
+
+    /// Returns the bytes of memory the ARC would release under memory pressure.
+    pub fn shrinkable(&self) -> u64 {
+        self.size.saturating_sub(self.c_min)
+    }
+
+    fn accesses(&self) -> u64 {
+        self.hits.saturating_add(self.misses)
+    }
+
+    fn hits(&self) -> u64 {
+        self.hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::zfs::ZfsArc;
+    use crate::SimpleServerStatus;
+    use std::path::Path;
+
+    #[test]
+    fn zfs() {
+        let mut status = SimpleServerStatus::default().with_zfs_arc_reclaimable(true);
+
+        assert_eq!(status.zfs_arc(), None);
+        assert_eq!(status.zfs_arc_hit_ratio(), None);
+
+        status.update().unwrap();
+        status.update().unwrap();
+
+        if !Path::new("/proc/spl/kstat/zfs/arcstats").exists() {
+            assert_eq!(status.zfs_arc(), None);
+            return;
+        }
+        let arc = status.zfs_arc().unwrap();
+        println!("zfs_arc: {:?}", arc);
+        assert!(arc.c_min <= arc.c_max);
+        if let Some(hit_ratio) = status.zfs_arc_hit_ratio() {
+            println!("zfs_arc_hit_ratio: {}", hit_ratio);
+            assert!((0.0..=1.0).contains(&hit_ratio));
+        }
+    }
+
+    #[test]
+    fn zfs_parse() {
+        let arc = ZfsArc::parse(
+            "13 1 0x01 123 33456 4564 6456
+name                            type data
+hits                            4    900
+misses                          4    100
+c                               4    6000
+c_min                           4    1000
+c_max                           4    8000
+size                            4    5000
+",
+        )
+        .unwrap();
+        assert_eq!(
+            arc,
+            ZfsArc {
+                size: 5000,
+                c_min: 1000,
+                c_max: 8000,
+                hits: 900,
+                misses: 100,
+            }
+        );
+        assert_eq!(arc.shrinkable(), 4000);
+    }
+}